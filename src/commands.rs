@@ -1,11 +1,21 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
 use std::str::FromStr;
-use tracing::warn;
+use tracing::info;
+use twilight_http::{request::Request, routing::Route};
 use twilight_model::{
-    application::{command::CommandType, interaction::InteractionContextType},
+    application::{
+        command::{Command, CommandType},
+        interaction::InteractionContextType,
+    },
+    guild::Permissions,
+    id::{marker::GuildMarker, Id},
     oauth::ApplicationIntegrationType,
 };
-use twilight_util::builder::command::{CommandBuilder, IntegerBuilder, StringBuilder, UserBuilder};
+use twilight_util::builder::command::{
+    AttachmentBuilder, BooleanBuilder, ChannelBuilder, CommandBuilder, IntegerBuilder,
+    StringBuilder, SubCommandBuilder, SubCommandGroupBuilder, UserBuilder,
+};
 
 use crate::{constants::limit, models::app_state::AppState};
 
@@ -29,17 +39,72 @@ const BOOK_OF_ANSWERS: CommandNamePair = CommandNamePair {
     names: &["bookofanswers", "boa"],
     id: Marker::BookOfAnswers,
 };
+const BIRTHDAY: CommandNamePair = CommandNamePair {
+    names: &["birthday", "bd"],
+    id: Marker::Birthday,
+};
+const HOROSCOPE: CommandNamePair = CommandNamePair {
+    names: &["horoscope", "hs"],
+    id: Marker::Horoscope,
+};
+const I_CHING: CommandNamePair = CommandNamePair {
+    names: &["iching", "ic"],
+    id: Marker::IChing,
+};
+const RUNES: CommandNamePair = CommandNamePair {
+    names: &["runes", "rn"],
+    id: Marker::Runes,
+};
+const NUMEROLOGY: CommandNamePair = CommandNamePair {
+    names: &["numerology", "nm"],
+    id: Marker::Numerology,
+};
+const ASK: CommandNamePair = CommandNamePair {
+    names: &["ask", "8ball"],
+    id: Marker::Ask,
+};
+const CONFIG: CommandNamePair = CommandNamePair {
+    names: &["config"],
+    id: Marker::Config,
+};
+const BOOK: CommandNamePair = CommandNamePair {
+    names: &["book"],
+    id: Marker::Book,
+};
+const DECK: CommandNamePair = CommandNamePair {
+    names: &["deck"],
+    id: Marker::Deck,
+};
 const ABOUT: CommandNamePair = CommandNamePair {
     names: &["about"],
     id: Marker::About,
 };
+const HELP: CommandNamePair = CommandNamePair {
+    names: &["help"],
+    id: Marker::Help,
+};
+const ADMIN: CommandNamePair = CommandNamePair {
+    names: &["admin"],
+    id: Marker::Admin,
+};
 const CMD_NAMES: &[CommandNamePair] = &[
     RANDOM_PICK,
     CLOW_CARDS,
     RELA_CALC,
     DICE,
     BOOK_OF_ANSWERS,
+    BIRTHDAY,
+    HOROSCOPE,
+    I_CHING,
+    RUNES,
+    NUMEROLOGY,
+    ASK,
+    CONFIG,
+    BOOK,
+    DECK,
     ABOUT,
+    HELP,
+    ADMIN,
 ];
 
 struct CommandNamePair<'a> {
@@ -47,14 +112,46 @@ struct CommandNamePair<'a> {
     id: Marker,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Marker {
     RandomPick,
     BookOfAnswers,
     DrawClowcard,
     RelationshipCalculator,
     Dice,
+    Birthday,
+    Horoscope,
+    IChing,
+    Runes,
+    Numerology,
+    Ask,
+    Config,
+    Book,
+    Deck,
     About,
+    Help,
+    Admin,
+}
+
+impl Marker {
+    pub fn all() -> impl Iterator<Item = Self> {
+        CMD_NAMES.iter().map(|pair| pair.id)
+    }
+    /// The primary command name, then the aliases of the message command
+    pub fn names(self) -> &'static [&'static str] {
+        CMD_NAMES
+            .iter()
+            .find(|pair| pair.id == self)
+            .map_or(&[], |pair| pair.names)
+    }
+    /// The primary command name
+    pub fn name(self) -> &'static str {
+        self.names().first().copied().unwrap_or("unknown")
+    }
+    /// Whether guilds may turn the command off, `/config` stays on so it can be turned back on
+    pub fn toggleable(self) -> bool {
+        !matches!(self, Marker::Config | Marker::Help | Marker::Admin)
+    }
 }
 
 impl FromStr for Marker {
@@ -74,7 +171,14 @@ impl CommandRegister {
     pub fn new(state: AppState) -> Self {
         Self(state)
     }
-    pub async fn register(self) {
+    /// Where commands go, the dev guild updates instantly while global commands may take a while
+    fn scope(&self) -> Option<Id<GuildMarker>> {
+        self.0.config.discord.dev_guild
+    }
+
+    pub fn commands(&self) -> Vec<Command> {
+        let limits = &self.0.config.limits;
+
         // Draw Clow Cards
         let drawclow = CommandBuilder::new(
             CLOW_CARDS.names[0],
@@ -85,8 +189,9 @@ impl CommandRegister {
         .option(
             IntegerBuilder::new("amount", "số lượng")
                 .min_value(1)
-                .max_value(limit::MAX_CLOW),
+                .max_value(i64::from(limits.max_clow)),
         )
+        .option(StringBuilder::new("deck", "bộ bài").autocomplete(true))
         .build();
 
         // Randomly Pick
@@ -109,6 +214,18 @@ impl CommandRegister {
             CommandType::ChatInput,
         )
         .option(StringBuilder::new("prompt", "nội dung"))
+        .option(StringBuilder::new("category", "chủ đề").choices([
+            ("Lời khuyên", "advice"),
+            ("Dự đoán", "prediction"),
+            ("Câu trả lời", "answer"),
+            ("Thời điểm", "timing"),
+        ]))
+        .option(StringBuilder::new("language", "ngôn ngữ").choices([
+            ("Tiếng Việt", "vi"),
+            ("English", "en"),
+            ("Song ngữ", "both"),
+        ]))
+        .option(StringBuilder::new("book", "sách riêng của server").autocomplete(true))
         .build();
 
         // Rolling Dice command
@@ -116,7 +233,7 @@ impl CommandRegister {
             .option(
                 IntegerBuilder::new("amount", "số lượng")
                     .min_value(1)
-                    .max_value(limit::MAX_DICE),
+                    .max_value(i64::from(limits.max_dice)),
             )
             .build();
 
@@ -130,12 +247,220 @@ impl CommandRegister {
         .option(UserBuilder::new("another_user", "another_user"))
         .build();
 
+        // Birthday command
+        let birthday = CommandBuilder::new(
+            BIRTHDAY.names[0],
+            "lưu ngày sinh của bạn",
+            CommandType::ChatInput,
+        )
+        .option(
+            SubCommandBuilder::new("set", "đặt ngày sinh")
+                .option(
+                    IntegerBuilder::new("day", "ngày")
+                        .min_value(1)
+                        .max_value(31)
+                        .required(true),
+                )
+                .option(
+                    IntegerBuilder::new("month", "tháng")
+                        .min_value(1)
+                        .max_value(12)
+                        .required(true),
+                )
+                .option(
+                    IntegerBuilder::new("year", "năm")
+                        .min_value(1900)
+                        .max_value(2100),
+                ),
+        )
+        .option(SubCommandBuilder::new("show", "xem ngày sinh đã lưu"))
+        .option(SubCommandBuilder::new("remove", "xoá ngày sinh đã lưu"))
+        .build();
+
+        // Horoscope command
+        let horoscope = CommandBuilder::new(
+            HOROSCOPE.names[0],
+            "xem tử vi cung hoàng đạo hôm nay",
+            CommandType::ChatInput,
+        )
+        .option(UserBuilder::new("user", "user"))
+        .build();
+
+        // I Ching command
+        let iching = CommandBuilder::new(
+            I_CHING.names[0],
+            "gieo quẻ Kinh Dịch",
+            CommandType::ChatInput,
+        )
+        .option(StringBuilder::new("question", "câu hỏi"))
+        .build();
+
+        // Rune casting command
+        let runes = CommandBuilder::new(
+            RUNES.names[0],
+            "gieo đá rune Elder Futhark",
+            CommandType::ChatInput,
+        )
+        .option(
+            IntegerBuilder::new("count", "số lượng")
+                .min_value(1)
+                .max_value(i64::from(limits.max_runes)),
+        )
+        .option(StringBuilder::new("prompt", "nội dung"))
+        .option(BooleanBuilder::new(
+            "norns",
+            "trải bài Norns: quá khứ, hiện tại, tương lai",
+        ))
+        .build();
+
+        // Numerology command
+        let numerology = CommandBuilder::new(
+            NUMEROLOGY.names[0],
+            "thần số học từ họ tên và ngày sinh",
+            CommandType::ChatInput,
+        )
+        .option(
+            SubCommandBuilder::new("reading", "xem các con số của bạn")
+                .option(StringBuilder::new("name", "họ và tên").required(true))
+                .option(StringBuilder::new("date", "ngày sinh (dd/mm/yyyy)").required(true)),
+        )
+        .option(
+            SubCommandBuilder::new("compare", "so sánh số chủ đạo giữa 2 users")
+                .option(UserBuilder::new("user", "user").required(true))
+                .option(UserBuilder::new("another_user", "another_user")),
+        )
+        .build();
+
+        // Yes/No oracle command
+        let ask = CommandBuilder::new(ASK.names[0], "hỏi một câu có/không", CommandType::ChatInput)
+            .option(StringBuilder::new("question", "câu hỏi").required(true))
+            .build();
+
+        // Guild config command
+        let mut config = CommandBuilder::new(
+            CONFIG.names[0],
+            "cài đặt bot cho server",
+            CommandType::ChatInput,
+        )
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .option(
+            SubCommandBuilder::new("commands", "bật/tắt lệnh trong server hoặc một kênh")
+                .option(
+                    StringBuilder::new("command", "lệnh, bỏ trống để xem cài đặt hiện tại")
+                        .choices(
+                            Marker::all()
+                                .filter(|marker| marker.toggleable())
+                                .map(|marker| (marker.name(), marker.name())),
+                        ),
+                )
+                .option(StringBuilder::new("state", "trạng thái").choices([
+                    ("Bật", "on"),
+                    ("Tắt", "off"),
+                    ("Mặc định", "inherit"),
+                ]))
+                .option(ChannelBuilder::new("channel", "chỉ áp dụng cho kênh này")),
+        )
+        .option(
+            SubCommandBuilder::new("oracle", "tỉ lệ câu trả lời của /ask")
+                .option(
+                    IntegerBuilder::new("positive", "tích cực")
+                        .min_value(0)
+                        .max_value(100)
+                        .required(true),
+                )
+                .option(
+                    IntegerBuilder::new("neutral", "trung lập")
+                        .min_value(0)
+                        .max_value(100)
+                        .required(true),
+                )
+                .option(
+                    IntegerBuilder::new("negative", "tiêu cực")
+                        .min_value(0)
+                        .max_value(100)
+                        .required(true),
+                ),
+        )
+        .build();
+        config.contexts = Some(vec![InteractionContextType::Guild]);
+        config.integration_types = Some(vec![ApplicationIntegrationType::GuildInstall]);
+
+        // Guild answer books command
+        let mut book = CommandBuilder::new(
+            BOOK.names[0],
+            "quản lý sách trả lời riêng của server",
+            CommandType::ChatInput,
+        )
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .option(
+            SubCommandBuilder::new("upload", "tải lên sách mới (.txt hoặc .json)")
+                .option(
+                    StringBuilder::new("name", "tên sách")
+                        .max_length(limit::MAX_UPLOAD_NAME)
+                        .required(true),
+                )
+                .option(AttachmentBuilder::new("file", "tệp sách").required(true)),
+        )
+        .option(
+            SubCommandBuilder::new("remove", "xoá một sách").option(
+                StringBuilder::new("book", "tên sách")
+                    .autocomplete(true)
+                    .required(true),
+            ),
+        )
+        .option(SubCommandBuilder::new("list", "danh sách sách của server"))
+        .build();
+        book.contexts = Some(vec![InteractionContextType::Guild]);
+        book.integration_types = Some(vec![ApplicationIntegrationType::GuildInstall]);
+
+        // Guild card decks command
+        let mut deck = CommandBuilder::new(
+            DECK.names[0],
+            "quản lý bộ bài riêng của server",
+            CommandType::ChatInput,
+        )
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .option(
+            SubCommandBuilder::new("upload", "tải lên bộ bài mới (.json)")
+                .option(
+                    StringBuilder::new("name", "tên bộ bài")
+                        .max_length(limit::MAX_UPLOAD_NAME)
+                        .required(true),
+                )
+                .option(AttachmentBuilder::new("file", "tệp bộ bài").required(true)),
+        )
+        .option(
+            SubCommandBuilder::new("remove", "xoá một bộ bài").option(
+                StringBuilder::new("deck", "tên bộ bài")
+                    .autocomplete(true)
+                    .required(true),
+            ),
+        )
+        .option(SubCommandBuilder::new(
+            "list",
+            "danh sách bộ bài của server",
+        ))
+        .build();
+        deck.contexts = Some(vec![InteractionContextType::Guild]);
+        deck.integration_types = Some(vec![ApplicationIntegrationType::GuildInstall]);
+
         // About command
         let about =
             CommandBuilder::new(ABOUT.names[0], "thông tin về bot", CommandType::ChatInput).build();
 
+        // Help command
+        let help = CommandBuilder::new(
+            HELP.names[0],
+            "các lệnh dùng được ở đây",
+            CommandType::ChatInput,
+        )
+        .build();
+
         // Adjust command scope
-        let mut commands = [about, boa, dice, drawclow, pick, relacalc];
+        let mut commands = vec![
+            about, ask, birthday, boa, dice, drawclow, help, horoscope, iching, numerology, pick,
+            relacalc, runes,
+        ];
         commands.iter_mut().for_each(|cmd| {
             cmd.contexts = Some(vec![
                 InteractionContextType::BotDm,
@@ -147,14 +472,216 @@ impl CommandRegister {
                 ApplicationIntegrationType::UserInstall,
             ]);
         });
+        // Guild-only commands
+        commands.extend([book, config, deck]);
+
+        // Guild commands cannot be installed elsewhere
+        if self.scope().is_some() {
+            commands.iter_mut().for_each(|cmd| {
+                cmd.contexts = None;
+                cmd.integration_types = None;
+            });
+        }
+        commands
+    }
+
+    /// Owner tooling, only registered in `admin.guild`
+    fn admin_command() -> Command {
+        let kind = || {
+            StringBuilder::new("kind", "loại")
+                .choices([("User", "user"), ("Server", "guild")])
+                .required(true)
+        };
+        let id = || StringBuilder::new("id", "id").required(true);
+        CommandBuilder::new(
+            ADMIN.names[0],
+            "công cụ cho chủ bot",
+            CommandType::ChatInput,
+        )
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .option(SubCommandBuilder::new(
+            "stats",
+            "thời gian chạy, số server và số lệnh đã dùng",
+        ))
+        .option(SubCommandBuilder::new("reload", "tải lại dữ liệu tĩnh"))
+        .option(
+            SubCommandBuilder::new("announce", "gửi thông báo")
+                .option(
+                    StringBuilder::new("message", "nội dung")
+                        .max_length(2000)
+                        .required(true),
+                )
+                .option(StringBuilder::new(
+                    "channel",
+                    "id kênh, bỏ trống để gửi tới kênh hệ thống của mọi server",
+                )),
+        )
+        .option(
+            SubCommandGroupBuilder::new("blocklist", "danh sách chặn").subcommands([
+                SubCommandBuilder::new("add", "chặn một user hoặc server")
+                    .option(kind())
+                    .option(id()),
+                SubCommandBuilder::new("remove", "bỏ chặn một user hoặc server")
+                    .option(kind())
+                    .option(id()),
+                SubCommandBuilder::new("list", "xem danh sách chặn"),
+            ]),
+        )
+        .option(SubCommandBuilder::new("shutdown", "tắt bot"))
+        .build()
+    }
 
-        // Register commands
-        let _ = self
+    async fn existing(&self, scope: Option<Id<GuildMarker>>) -> Result<Vec<Command>> {
+        let interaction = self.0.bot.interaction(self.0.info.appid);
+        let commands = match scope {
+            Some(guild) => interaction.guild_commands(guild).await?,
+            None => interaction.global_commands().await?,
+        };
+        Ok(commands.model().await?)
+    }
+
+    /// Create or update the changed commands and delete the stale ones, unchanged ones are left alone
+    pub async fn register(self) -> Result<()> {
+        let scope = self.scope();
+        let mut commands = self.commands();
+        match self.0.config.admin.guild {
+            Some(guild) if Some(guild) == scope => commands.push(Self::admin_command()),
+            // The admin guild may have commands of its own, so only `/admin` is touched there
+            Some(guild) => {
+                self.sync(Some(guild), vec![Self::admin_command()], false)
+                    .await?
+            }
+            None => {}
+        }
+        self.sync(scope, commands, true).await
+    }
+
+    /// Create or update `commands` in `scope`, and delete the others there when `prune` is set
+    async fn sync(
+        &self,
+        scope: Option<Id<GuildMarker>>,
+        commands: Vec<Command>,
+        prune: bool,
+    ) -> Result<()> {
+        let appid = self.0.info.appid;
+        let interaction = self.0.bot.interaction(appid);
+        let mut existing = self.existing(scope).await?;
+        let (mut created, mut updated, mut unchanged) = (0, 0, 0);
+
+        for command in commands {
+            let current = existing
+                .iter()
+                .position(|cmd| cmd.name == command.name && cmd.kind == command.kind)
+                .map(|pos| existing.swap_remove(pos));
+            match &current {
+                Some(current) if normalize(current) == normalize(&command) => {
+                    unchanged += 1;
+                    continue;
+                }
+                Some(_) => updated += 1,
+                None => created += 1,
+            }
+            // Creating a command with an existing name overwrites it
+            let route = match scope {
+                Some(guild) => Route::CreateGuildCommand {
+                    application_id: appid.get(),
+                    guild_id: guild.get(),
+                },
+                None => Route::CreateGlobalCommand {
+                    application_id: appid.get(),
+                },
+            };
+            let request = Request::builder(&route).json(&command).build()?;
+            self.0.bot.request::<Command>(request).await?;
+        }
+
+        // Whatever is left is no longer provided by the bot
+        if !prune {
+            existing.clear();
+        }
+        let deleted = existing.len();
+        for id in existing.into_iter().filter_map(|cmd| cmd.id) {
+            match scope {
+                Some(guild) => interaction.delete_guild_command(guild, id).await?,
+                None => interaction.delete_global_command(id).await?,
+            };
+        }
+
+        info!(
+            ?scope,
+            created, updated, deleted, unchanged, "registered commands"
+        );
+        Ok(())
+    }
+
+    /// Remove every command of the current scope, and `/admin` from the admin guild
+    pub async fn unregister(self) -> Result<()> {
+        let scope = self.scope();
+        let interaction = self.0.bot.interaction(self.0.info.appid);
+        match scope {
+            Some(guild) => interaction.set_guild_commands(guild, &[]).await?,
+            None => interaction.set_global_commands(&[]).await?,
+        };
+        info!(?scope, "unregistered commands");
+
+        // As in `register`, the other commands of the admin guild are not ours
+        if let Some(guild) = self
             .0
-            .bot
-            .interaction(self.0.info.appid)
-            .set_global_commands(&commands)
-            .await
-            .inspect_err(|err| warn!(?err, "fail to create commands"));
+            .config
+            .admin
+            .guild
+            .filter(|&guild| Some(guild) != scope)
+        {
+            let admin = Self::admin_command().name;
+            let stale = self.existing(Some(guild)).await?.into_iter();
+            for id in stale
+                .filter(|cmd| cmd.name == admin)
+                .filter_map(|cmd| cmd.id)
+            {
+                interaction.delete_guild_command(guild, id).await?;
+            }
+            info!(admin_guild = ?guild, "unregistered `/admin`");
+        }
+        Ok(())
+    }
+}
+
+/// The fields Discord stores for a command, without ids, versions and defaults
+fn normalize(command: &Command) -> Value {
+    const KEYS: &[&str] = &[
+        "autocomplete",
+        "channel_types",
+        "choices",
+        "contexts",
+        "default_member_permissions",
+        "description",
+        "integration_types",
+        "max_length",
+        "max_value",
+        "min_length",
+        "min_value",
+        "name",
+        "nsfw",
+        "options",
+        "required",
+        "type",
+        "value",
+    ];
+    fn strip(value: Value) -> Value {
+        match value {
+            Value::Object(map) => map
+                .into_iter()
+                .filter(|(key, _)| KEYS.contains(&key.as_str()))
+                .map(|(key, value)| (key, strip(value)))
+                .filter(|(_, value)| match value {
+                    Value::Null | Value::Bool(false) => false,
+                    Value::Array(items) => !items.is_empty(),
+                    _ => true,
+                })
+                .collect(),
+            Value::Array(items) => items.into_iter().map(strip).collect(),
+            value => value,
+        }
     }
+    strip(serde_json::to_value(command).unwrap_or_default())
 }
@@ -0,0 +1,303 @@
+use crate::models::{
+    assets::{Assets, CustomEmoji, Variants},
+    bot::Bot,
+    data_registry::{self, ASSETS},
+};
+use anyhow::{ensure, Context, Result};
+use base64::Engine;
+use rustc_hash::FxHashSet;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use twilight_model::guild::Emoji;
+
+/// Discord rejects larger emoji images
+const MAX_IMAGE_SIZE: u64 = 256 * 1024;
+
+/// An image from the emoji directory.
+///
+/// `dice-1.gif` is the asset `dice-1`, while `dice-1.2.gif` is its second variant.
+struct LocalEmoji {
+    asset: String,
+    variant: u32,
+    /// Name of the application emoji
+    name: String,
+    path: PathBuf,
+    mime: &'static str,
+}
+
+fn scan(dir: &Path) -> Result<Vec<LocalEmoji>> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("unable to read `{}`", dir.display()))?;
+    let mut names = FxHashSet::default();
+    let mut emojis = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        let mime = match ext.as_deref() {
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            _ => continue,
+        };
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .with_context(|| format!("`{}` should have a UTF-8 name", path.display()))?;
+        let (asset, variant) = match stem.rsplit_once('.') {
+            Some((asset, variant)) => match variant.parse() {
+                Ok(variant) => (asset, variant),
+                Err(_) => (stem, 0),
+            },
+            None => (stem, 0),
+        };
+        let name = stem
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        ensure!(
+            (2..=32).contains(&name.len()),
+            "`{}` emoji name should have 2 to 32 characters",
+            path.display()
+        );
+        ensure!(
+            names.insert(name.clone()),
+            "`{}` has the same emoji name `{name}` as another image",
+            path.display()
+        );
+        emojis.push(LocalEmoji {
+            asset: asset.to_owned(),
+            variant,
+            name,
+            path,
+            mime,
+        });
+    }
+    emojis.sort_by(|lhs, rhs| (&lhs.asset, lhs.variant).cmp(&(&rhs.asset, rhs.variant)));
+    Ok(emojis)
+}
+
+impl LocalEmoji {
+    fn data_uri(&self) -> Result<String> {
+        let size = std::fs::metadata(&self.path)?.len();
+        ensure!(
+            size <= MAX_IMAGE_SIZE,
+            "`{}` should be at most {} KiB",
+            self.path.display(),
+            MAX_IMAGE_SIZE / 1024
+        );
+        let bytes = std::fs::read(&self.path)?;
+        let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+        Ok(format!("data:{};base64,{data}", self.mime))
+    }
+}
+
+/// The manifest in the data directory when set, the repository copy otherwise
+fn default_manifest() -> PathBuf {
+    data_registry::override_target(ASSETS.name)
+        .unwrap_or_else(|| Path::new("static").join(ASSETS.name))
+}
+
+/// Upload the images in `dir` missing from the application emojis,
+/// then record every emoji id in the asset manifest.
+///
+/// Emojis are matched by name, so running it again uploads nothing.
+pub async fn run(dir: PathBuf, manifest: Option<PathBuf>, proxy: Option<String>) -> Result<()> {
+    let token = std::env::var("DISCORD_TOKEN").context("env var `DISCORD_TOKEN` should exists")?;
+    let bot = Bot::new_with_proxy(token, proxy);
+    let manifest = manifest.unwrap_or_else(default_manifest);
+    sync(&bot, &dir, &manifest).await?;
+    Ok(())
+}
+
+/// Same as [`run`] with an existing client, returns how many emojis were uploaded
+async fn sync(bot: &Bot, dir: &Path, manifest: &Path) -> Result<usize> {
+    let local = scan(dir)?;
+    let mut assets = if manifest.exists() {
+        Assets::from_json(&std::fs::read_to_string(manifest)?)
+            .with_context(|| format!("`{}` should be valid", manifest.display()))?
+    } else {
+        Assets::default()
+    };
+
+    let appid = bot.current_user().await?.model().await?.id.cast();
+    let remote = bot
+        .get_application_emojis(appid)
+        .await?
+        .model()
+        .await?
+        .items
+        .into_iter()
+        .map(|emoji| (emoji.name.clone(), emoji))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut synced = BTreeMap::<_, Vec<_>>::new();
+    let mut uploaded = 0;
+    for emoji in &local {
+        let Emoji { id, animated, .. } = match remote.get(&emoji.name) {
+            Some(existing) => existing.clone(),
+            None => {
+                let image = emoji.data_uri()?;
+                let created = bot
+                    .add_application_emoji(appid, &emoji.name, &image)
+                    .await
+                    .with_context(|| format!("unable to upload `{}`", emoji.path.display()))?
+                    .model()
+                    .await?;
+                println!("uploaded `{}` as {}", emoji.name, created.id);
+                uploaded += 1;
+                created
+            }
+        };
+        synced
+            .entry(emoji.asset.clone())
+            .or_default()
+            .push(CustomEmoji { id, animated });
+    }
+    for (asset, mut variants) in synced {
+        let variants = match variants.len() {
+            1 => Variants::One(variants.remove(0)),
+            _ => Variants::Many(variants),
+        };
+        assets.emojis.insert(asset, variants);
+    }
+
+    if let Some(parent) = manifest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(&assets)?;
+    std::fs::write(manifest, json + "\n")
+        .with_context(|| format!("unable to write `{}`", manifest.display()))?;
+    println!(
+        "{uploaded} uploaded, {} already present, manifest written to `{}`",
+        local.len() - uploaded,
+        manifest.display()
+    );
+    Ok(uploaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::{header, Method, Uri},
+        response::IntoResponse,
+        Router,
+    };
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    /// Empty directory unique to the test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("boothebot-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn touch(dir: &Path, files: &[&str]) {
+        for file in files {
+            std::fs::write(dir.join(file), b"image").unwrap();
+        }
+    }
+
+    #[test]
+    fn scan_variants() {
+        let dir = temp_dir("scan-variants");
+        touch(
+            &dir,
+            &["dice-1.gif", "dice-1.2.gif", "moon.full.png", "notes.txt"],
+        );
+        let emojis = scan(&dir).unwrap();
+        let emojis = emojis
+            .iter()
+            .map(|emoji| (emoji.asset.as_str(), emoji.variant, emoji.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            emojis,
+            [
+                ("dice-1", 0, "dice_1"),
+                ("dice-1", 2, "dice_1_2"),
+                ("moon.full", 0, "moon_full"),
+            ]
+        );
+    }
+
+    #[test]
+    fn scan_rejects_duplicate_names() {
+        let dir = temp_dir("scan-duplicates");
+        touch(&dir, &["new-moon.png", "new_moon.gif"]);
+        assert!(scan(&dir).is_err());
+    }
+
+    /// Stand-in for the Discord endpoints used by the sync
+    async fn mock_discord(emojis: Arc<Mutex<Vec<Value>>>) -> String {
+        let handler = move |method: Method, uri: Uri, body: String| {
+            let emojis = emojis.clone();
+            async move {
+                let path = uri.path();
+                let value = if path.ends_with("/users/@me") {
+                    json!({"id": "42", "username": "bot", "discriminator": "0000", "avatar": null, "bot": true, "mfa_enabled": false})
+                } else if method == Method::POST {
+                    let body = serde_json::from_str::<Value>(&body).unwrap();
+                    let mut emojis = emojis.lock().unwrap();
+                    let emoji = json!({
+                        "id": (900 + emojis.len()).to_string(),
+                        "name": body["name"],
+                        "animated": body["image"].as_str().unwrap().starts_with("data:image/gif"),
+                        "available": true,
+                        "managed": false,
+                        "require_colons": true,
+                        "roles": [],
+                    });
+                    emojis.push(emoji.clone());
+                    emoji
+                } else {
+                    json!({"items": *emojis.lock().unwrap()})
+                };
+                (
+                    [(header::CONTENT_TYPE, "application/json")],
+                    value.to_string(),
+                )
+                    .into_response()
+            }
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().fallback(handler))
+                .await
+                .unwrap()
+        });
+        addr.to_string()
+    }
+
+    #[tokio::test]
+    async fn second_sync_uploads_nothing() {
+        let dir = temp_dir("sync");
+        let images = dir.join("images");
+        std::fs::create_dir_all(&images).unwrap();
+        touch(&images, &["dice-1.png", "dice-1.2.gif", "moon.png"]);
+        let manifest = dir.join("Assets.json");
+
+        let emojis = Arc::new(Mutex::new(Vec::new()));
+        let proxy = mock_discord(emojis.clone()).await;
+        let bot = Bot::new_with_proxy("token", Some(proxy));
+
+        assert_eq!(sync(&bot, &images, &manifest).await.unwrap(), 3);
+        let first = std::fs::read_to_string(&manifest).unwrap();
+        assert_eq!(sync(&bot, &images, &manifest).await.unwrap(), 0);
+        let second = std::fs::read_to_string(&manifest).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(emojis.lock().unwrap().len(), 3);
+
+        let assets = Assets::from_json(&second).unwrap();
+        assert!(matches!(assets.emojis["dice-1"], Variants::Many(ref many) if many.len() == 2));
+        assert!(matches!(assets.emojis["moon"], Variants::One(_)));
+    }
+}
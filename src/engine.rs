@@ -1,34 +1,105 @@
-use crate::models::app_state::AppState;
-use crate::{commands::CommandRegister, handler::Handler};
-use tracing::{info, warn};
-use twilight_gateway::{Event, EventTypeFlags, Intents, Shard, ShardId, StreamExt};
+use crate::models::{
+    app_state::AppState, config::Config, error_report::ErrorReport, gateway_session::GatewaySession,
+};
+use crate::{
+    commands::CommandRegister,
+    handler::{Handler, InputRaw},
+    server,
+};
+use std::{io::Write, time::Duration};
+use tokio::task::{JoinError, JoinSet};
+use tracing::{debug, error, info, warn, Instrument};
+use twilight_gateway::{
+    CloseFrame, ConfigBuilder, Event, EventTypeFlags, Shard, ShardId, StreamExt,
+};
 use twilight_model::gateway::payload::incoming::{InteractionCreate, MessageCreate};
 
+/// Why a command is not run
+#[derive(Debug)]
+enum Rejection {
+    /// The user or guild is on `/admin blocklist`
+    Blocked,
+    /// Turned off with `/config commands`
+    Disabled,
+    RateLimited(Duration),
+}
+
 pub struct Engine {
     state: AppState,
     shard: Shard,
 }
 
 impl Engine {
-    pub async fn new(token: impl Into<String>) -> anyhow::Result<Self> {
+    pub async fn new(token: impl Into<String>, config: Config) -> anyhow::Result<Self> {
         let token = token.into();
-        let state = AppState::new_with_token(token.clone()).await?;
-        let intents = Intents::GUILD_MESSAGES | Intents::DIRECT_MESSAGES | Intents::MESSAGE_CONTENT;
-        let shard = Shard::new(ShardId::ONE, token, intents);
+        let intents = config.discord.intents();
+        let state = AppState::new_with_token(token.clone(), config).await?;
+
+        // Resume the previous session so the events sent in between are replayed,
+        // the shard identifies again by itself if Discord rejects it
+        let mut builder = ConfigBuilder::new(token, intents);
+        if let Some(saved) = GatewaySession::take(&state.config.paths.session) {
+            info!(
+                sequence = saved.session.sequence(),
+                "resuming gateway session"
+            );
+            builder = builder.session(saved.session);
+            if let Some(resume_url) = saved.resume_url {
+                builder = builder.resume_url(resume_url);
+            }
+        }
+        let shard = Shard::with_config(ShardId::ONE, builder.build());
 
         Ok(Self { state, shard })
     }
     pub async fn run(self) {
         let Self { state, mut shard } = self;
 
-        CommandRegister::new(state.clone()).register().await;
+        match CommandRegister::new(state.clone()).register().await {
+            Ok(()) => state.health.commands_registered(),
+            Err(err) => {
+                warn!(?err, "fail to register commands");
+                ErrorReport::new("register commands", err.as_ref()).send(&state);
+            }
+        }
+
+        if let Some(listen) = state.config.server.listen {
+            tokio::spawn(server::serve(state.clone(), listen));
+        }
 
         let wanted_event_types = EventTypeFlags::READY
+            | EventTypeFlags::RESUMED
+            | EventTypeFlags::GATEWAY_HEARTBEAT_ACK
             | EventTypeFlags::MESSAGE_CREATE
-            | EventTypeFlags::INTERACTION_CREATE;
+            | EventTypeFlags::INTERACTION_CREATE
+            | EventTypeFlags::GUILD_CREATE
+            | EventTypeFlags::GUILD_DELETE;
+
+        // Handlers in flight, drained on shutdown
+        let mut tasks = JoinSet::new();
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        // Sessions after the first one are reconnects
+        let mut connected = false;
 
-        while let Some(item) = shard.next_event(wanted_event_types).await {
+        loop {
+            let item = tokio::select! {
+                () = &mut shutdown => break,
+                // `/admin shutdown`
+                () = state.shutdown.notified() => break,
+                // Reap finished handlers so the set does not grow forever
+                Some(result) = tasks.join_next(), if !tasks.is_empty() => {
+                    Self::reap(&state, result);
+                    continue;
+                }
+                item = shard.next_event(wanted_event_types) => item,
+            };
+            let Some(item) = item else {
+                break;
+            };
             let Ok(event) = item else {
+                // The shard reconnects by itself
+                state.health.disconnected();
                 warn!(source = ?item.unwrap_err(), "fail to recieve event");
                 continue;
             };
@@ -41,17 +112,167 @@ impl Engine {
                         continue;
                     }
 
-                    tokio::spawn(Self::message_create(state.clone(), msg));
+                    tasks.spawn(Self::message_create(state.clone(), msg));
                 }
                 Event::InteractionCreate(itr) => {
-                    tokio::spawn(Self::interaction_create(state.clone(), itr));
+                    tasks.spawn(Self::interaction_create(state.clone(), itr));
                 }
                 Event::Ready(ready) => {
                     info!("{} is ready!", ready.user.name);
+                    *state.guilds.write().unwrap() =
+                        ready.guilds.iter().map(|guild| guild.id).collect();
+                    state.health.connected();
+                    if std::mem::replace(&mut connected, true) {
+                        state.metrics.gateway_reconnect("identify");
+                    }
+                }
+                Event::Resumed => {
+                    info!("gateway session resumed");
+                    state.health.connected();
+                    if std::mem::replace(&mut connected, true) {
+                        state.metrics.gateway_reconnect("resume");
+                    }
+                }
+                Event::GuildCreate(guild) => {
+                    let id = guild.id();
+                    if state.storage.read().blocklist.guilds.contains(&id) {
+                        info!(guild = %id, "leaving blocked guild");
+                        let state = state.clone();
+                        tasks.spawn(async move { state.bot.leave_blocked_guild(id).await });
+                    } else {
+                        state.guilds.write().unwrap().insert(id);
+                    }
+                }
+                // Unavailable guilds come back with GUILD_CREATE
+                Event::GuildDelete(guild) if !guild.unavailable.unwrap_or_default() => {
+                    state.guilds.write().unwrap().remove(&guild.id);
+                }
+                Event::GatewayClose(_) => state.health.disconnected(),
+                Event::GatewayHeartbeatAck => {
+                    if let Some(latency) = shard.latency().recent().first() {
+                        state.metrics.gateway_latency(*latency);
+                    }
                 }
                 _ => {}
             }
         }
+
+        // Events after this point are left for the next run to replay
+        let session = GatewaySession::capture(&shard);
+        Self::shutdown(state, shard, tasks, session).await;
+    }
+
+    /// Let in-flight handlers finish, then leave the gateway so the session can be resumed
+    async fn shutdown(
+        state: AppState,
+        mut shard: Shard,
+        mut tasks: JoinSet<()>,
+        session: Option<GatewaySession>,
+    ) {
+        let timeout = Duration::from_secs(state.config.shutdown.drain_timeout_secs);
+        info!(tasks = tasks.len(), "shutting down");
+
+        let drain = async {
+            while let Some(result) = tasks.join_next().await {
+                Self::reap(&state, result);
+            }
+        };
+        if tokio::time::timeout(timeout, drain).await.is_err() {
+            warn!(
+                tasks = tasks.len(),
+                "handlers did not finish in time, aborting"
+            );
+            tasks.abort_all();
+        }
+
+        // The close frame is only sent while the shard is polled
+        shard.close(CloseFrame::RESUME);
+        let close = async {
+            // Close messages are always yielded
+            while let Some(item) = shard.next_event(EventTypeFlags::empty()).await {
+                if let Ok(Event::GatewayClose(_)) = item {
+                    break;
+                }
+            }
+        };
+        if tokio::time::timeout(timeout, close).await.is_err() {
+            warn!("gateway did not close in time");
+        }
+
+        if let Some(session) = session {
+            let _ = session
+                .save(&state.config.paths.session)
+                .inspect_err(|err| warn!(?err, "unable to save gateway session"));
+        }
+        let _ = state
+            .storage
+            .flush()
+            .inspect_err(|err| warn!(?err, "unable to flush storage"));
+        info!("shut down");
+        let _ = std::io::stdout().flush();
+        let _ = std::io::stderr().flush();
+    }
+
+    /// Report handlers that panicked
+    fn reap(state: &AppState, result: Result<(), JoinError>) {
+        let Err(err) = result else {
+            return;
+        };
+        if !err.is_panic() {
+            return;
+        }
+        let payload = err.into_panic();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        error!(message, "handler panicked");
+        ErrorReport::panic(message.to_owned()).send(state);
+    }
+
+    /// Whether the command may run, or why it is rejected.
+    /// Checks the blocklist, then the `/config commands` toggles, then the rate limits,
+    /// before the command is parsed
+    fn gate(state: &AppState, raw: InputRaw) -> Result<(), Rejection> {
+        // Owners can not lock themselves out
+        if raw.user().is_none_or(|user| !state.is_owner(user))
+            && state
+                .storage
+                .read()
+                .blocklist
+                .is_blocked(raw.user(), raw.guild())
+        {
+            return Err(Rejection::Blocked);
+        }
+
+        // Autocomplete cannot be answered with a message, and costs nothing
+        if raw.path() == "autocomplete" {
+            return Ok(());
+        }
+        let (Some(marker), Some(user)) = (raw.marker(), raw.user()) else {
+            return Ok(());
+        };
+
+        if let Some(guild) = raw.guild() {
+            let storage = state.storage.read();
+            if storage
+                .command_toggles
+                .get(&guild)
+                .is_some_and(|toggles| !toggles.is_enabled(raw.channel(), marker))
+            {
+                return Err(Rejection::Disabled);
+            }
+        }
+
+        let config = &state.config.cooldown;
+        if state.is_owner(user) || config.bypass.contains(&user) || raw.is_admin() {
+            return Ok(());
+        }
+        state
+            .cooldowns
+            .check(config, user, raw.channel(), marker)
+            .map_err(Rejection::RateLimited)
     }
 
     async fn message_create(state: AppState, mut msg: Box<MessageCreate>) {
@@ -64,22 +285,116 @@ impl Engine {
             bytes[1..mention_str.len()].fill(b' ');
         }
 
-        // Parse command into future response
-        if let Some(fut) = Handler::new(state, &*msg).response_message_future() {
-            let _ = fut
-                .await
-                .inspect_err(|err| warn!(?err, "unable to reponse message command"));
+        let raw = InputRaw::from(&*msg);
+        async {
+            if let Err(rejection) = Self::gate(&state, raw) {
+                debug!(?rejection, "command rejected");
+                // Blocked users and disabled commands are ignored silently
+                if matches!(rejection, Rejection::RateLimited(_)) && state.config.cooldown.react {
+                    let emoji = state.assets.emoji("cooldown", "⏳");
+                    let _ = state
+                        .bot
+                        .create_reaction(msg.channel_id, msg.id, &emoji.request())
+                        .await
+                        .inspect_err(|err| warn!(?err, "unable to react to rate limited command"));
+                }
+                return;
+            }
+
+            // Parse command into future response
+            let handler = Handler::new(state.clone(), raw);
+            if let Some(fut) = handler.response_message_future() {
+                // Only parsed commands are counted and timed
+                let _timer = raw
+                    .marker()
+                    .map(|marker| state.metrics.command(marker, raw.path()));
+                let _ = fut.await.inspect_err(|err| {
+                    state.metrics.response_error(raw.path(), err);
+                    warn!(?err, "unable to reponse message command");
+                    ErrorReport::new("message command", err)
+                        .with_input(raw.marker(), raw.input())
+                        .send(&state);
+                });
+            }
         }
+        .instrument(raw.span())
+        .await
     }
 
     async fn interaction_create(state: AppState, itr: Box<InteractionCreate>) {
-        // Parse command into future response
-        if let Some(fut) = Handler::new(state, &*itr).response_interaction_future() {
-            let _ = fut
-                .await
-                .inspect_err(|err| warn!(?err, "unable to reponse interaction command"));
-        } else {
-            warn!("unable to parse command")
+        let raw = InputRaw::from(&*itr);
+        async {
+            if let Err(rejection) = Self::gate(&state, raw) {
+                debug!(?rejection, "command rejected");
+                let error = match rejection {
+                    // Autocomplete cannot be answered with a message
+                    Rejection::Blocked if raw.path() == "autocomplete" => return,
+                    Rejection::Blocked => "Bạn không thể sử dụng bot này".to_owned(),
+                    Rejection::Disabled => "Lệnh này đã bị tắt ở đây".to_owned(),
+                    Rejection::RateLimited(wait) => format!(
+                        "Bạn thao tác nhanh quá, hãy thử lại sau {} giây nhé",
+                        wait.as_secs_f64().ceil()
+                    ),
+                };
+                if let Some(fut) =
+                    Handler::reject(state.clone(), raw, error).response_interaction_future()
+                {
+                    let _ = fut
+                        .await
+                        .inspect_err(|err| warn!(?err, "unable to reponse rejected command"));
+                }
+                return;
+            }
+
+            // Parse command into future response
+            let mut handler = Handler::new(state.clone(), raw);
+            handler.prefetch().await;
+            if let Some(fut) = handler.response_interaction_future() {
+                let _timer = raw
+                    .marker()
+                    .map(|marker| state.metrics.command(marker, raw.path()));
+                let _ = fut.await.inspect_err(|err| {
+                    state.metrics.response_error(raw.path(), err);
+                    warn!(?err, "unable to reponse interaction command");
+                    ErrorReport::new("interaction command", err)
+                        .with_input(raw.marker(), raw.input())
+                        .send(&state);
+                });
+            } else {
+                warn!("unable to parse command")
+            }
         }
+        .instrument(raw.span())
+        .await
+    }
+}
+
+/// Resolves on Ctrl+C, or SIGTERM on Unix
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!(?err, "unable to listen for Ctrl+C");
+            std::future::pending().await
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(err) => {
+                warn!(?err, "unable to listen for SIGTERM");
+                std::future::pending().await
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
     }
 }
@@ -4,6 +4,7 @@ pub trait CommandOptionValueData {
     fn string(&self) -> Option<&str>;
     fn i64(&self) -> Option<i64>;
     fn usize(&self) -> Option<usize>;
+    fn bool(&self) -> Option<bool>;
 }
 
 impl CommandOptionValueData for CommandOptionValue {
@@ -24,4 +25,11 @@ impl CommandOptionValueData for CommandOptionValue {
     fn usize(&self) -> Option<usize> {
         Self::i64(self).map(|value| value as usize)
     }
+
+    fn bool(&self) -> Option<bool> {
+        match self {
+            CommandOptionValue::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
 }
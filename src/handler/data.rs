@@ -1,7 +1,22 @@
 use super::InputRaw;
+use crate::{
+    commands::Marker,
+    models::{
+        blocklist::BlockTarget,
+        book_of_answers::{Language, ToneDistribution},
+        command_toggles::Toggle,
+        zodiac::BirthDate,
+    },
+};
 use arrayvec::ArrayVec;
 use std::borrow::Cow;
-use twilight_model::id::{marker::UserMarker, Id};
+use twilight_model::{
+    channel::Attachment,
+    id::{
+        marker::{ChannelMarker, GuildMarker, UserMarker},
+        Id,
+    },
+};
 
 #[derive(Debug)]
 pub enum Data<'a> {
@@ -11,7 +26,20 @@ pub enum Data<'a> {
     ClowCardInfo(ClowCardInfo<'a>),
     Dice(Dice),
     LoveCalculator(RelationshipCalculator),
+    Birthday(Birthday),
+    Horoscope(Horoscope),
+    IChing(IChing<'a>),
+    Runes(Runes<'a>),
+    Numerology(Numerology<'a>),
+    Ask(Ask<'a>),
+    Config(Config),
+    Book(Book<'a>),
+    Deck(Deck<'a>),
+    Autocomplete(Autocomplete<'a>),
+    RuneInfo(RuneInfo<'a>),
     About(About),
+    Help(Help),
+    Admin(Admin<'a>),
     None,
     Error(Error),
 }
@@ -26,6 +54,12 @@ pub struct RandomPick<'a> {
 pub struct BookOfAnswers<'a> {
     pub prompt: Option<&'a str>,
     pub author: Option<Id<UserMarker>>,
+    pub category: Option<&'a str>,
+    pub guild: Option<Id<GuildMarker>>,
+    /// Guild book to draw from, the global book is used when missing
+    pub book: Option<&'a str>,
+    /// `None` renders both languages
+    pub language: Option<Language>,
     pub show_prompt: bool,
 }
 
@@ -39,16 +73,139 @@ pub struct DrawClowcard<'a> {
     pub prompt: Option<&'a str>,
     pub author: Option<Id<UserMarker>>,
     pub amount: Option<usize>,
+    pub guild: Option<Id<GuildMarker>>,
+    /// Built-in or guild deck id, the Clow deck is used when missing
+    pub deck: Option<&'a str>,
     pub show_prompt: bool,
 }
 #[derive(Debug)]
 pub struct ClowCardInfo<'a> {
+    pub guild: Option<Id<GuildMarker>>,
+    pub deck: Cow<'a, str>,
     pub name: Cow<'a, str>,
 }
 
 #[derive(Debug)]
 pub struct RelationshipCalculator {
     pub targets: ArrayVec<Id<UserMarker>, 2>,
+    /// Compare life path numbers as well
+    pub numerology: bool,
+}
+
+#[derive(Debug)]
+pub struct Birthday {
+    pub author: Option<Id<UserMarker>>,
+    pub action: BirthdayAction,
+}
+
+#[derive(Debug)]
+pub enum BirthdayAction {
+    Set(BirthDate),
+    Show,
+    Remove,
+}
+
+#[derive(Debug)]
+pub struct Horoscope {
+    pub target: Option<Id<UserMarker>>,
+    pub author: Option<Id<UserMarker>>,
+}
+
+#[derive(Debug)]
+pub struct IChing<'a> {
+    pub prompt: Option<&'a str>,
+    pub author: Option<Id<UserMarker>>,
+    pub show_prompt: bool,
+}
+
+#[derive(Debug)]
+pub struct Runes<'a> {
+    pub prompt: Option<&'a str>,
+    pub author: Option<Id<UserMarker>>,
+    pub amount: Option<usize>,
+    pub norns: bool,
+    pub show_prompt: bool,
+}
+
+#[derive(Debug)]
+pub struct RuneInfo<'a> {
+    pub name: Cow<'a, str>,
+    pub reversed: bool,
+}
+
+#[derive(Debug)]
+pub struct Numerology<'a> {
+    pub name: &'a str,
+    pub date: BirthDate,
+}
+
+#[derive(Debug)]
+pub struct Ask<'a> {
+    pub prompt: Option<&'a str>,
+    pub author: Option<Id<UserMarker>>,
+    pub guild: Option<Id<GuildMarker>>,
+    pub language: Option<Language>,
+    pub show_prompt: bool,
+}
+
+#[derive(Debug)]
+pub struct Config {
+    pub guild: Option<Id<GuildMarker>>,
+    pub action: ConfigAction,
+}
+
+#[derive(Debug)]
+pub enum ConfigAction {
+    OracleTone(ToneDistribution),
+    /// Shows the current settings when `command` is missing
+    Commands {
+        command: Option<Marker>,
+        toggle: Option<Toggle>,
+        /// The whole guild when missing
+        channel: Option<Id<ChannelMarker>>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Book<'a> {
+    pub guild: Option<Id<GuildMarker>>,
+    pub action: BookAction<'a>,
+}
+
+#[derive(Debug)]
+pub enum BookAction<'a> {
+    Upload(Upload<'a>),
+    Remove(&'a str),
+    List,
+}
+
+#[derive(Debug)]
+pub struct Deck<'a> {
+    pub guild: Option<Id<GuildMarker>>,
+    pub action: DeckAction<'a>,
+}
+
+#[derive(Debug)]
+pub enum DeckAction<'a> {
+    Upload(Upload<'a>),
+    Remove(&'a str),
+    List,
+}
+
+#[derive(Debug)]
+pub struct Upload<'a> {
+    pub name: &'a str,
+    pub attachment: Option<&'a Attachment>,
+    /// Downloaded by `Handler::prefetch`
+    pub content: Option<anyhow::Result<String>>,
+}
+
+/// Suggest guild books or decks for the focused option
+#[derive(Debug)]
+pub struct Autocomplete<'a> {
+    pub guild: Option<Id<GuildMarker>>,
+    pub option: &'a str,
+    pub input: &'a str,
 }
 
 #[derive(Debug)]
@@ -59,6 +216,34 @@ pub struct Error {
 #[derive(Debug)]
 pub struct About;
 
+#[derive(Debug)]
+pub struct Admin<'a> {
+    pub author: Id<UserMarker>,
+    pub action: AdminAction<'a>,
+}
+
+#[derive(Debug)]
+pub enum AdminAction<'a> {
+    Stats,
+    Reload,
+    Announce {
+        message: &'a str,
+        /// Every guild's system channel when missing
+        channel: Option<Id<ChannelMarker>>,
+    },
+    /// `None` when the id is invalid
+    BlocklistAdd(Option<BlockTarget>),
+    BlocklistRemove(Option<BlockTarget>),
+    BlocklistList,
+    Shutdown,
+}
+
+#[derive(Debug)]
+pub struct Help {
+    pub guild: Option<Id<GuildMarker>>,
+    pub channel: Option<Id<ChannelMarker>>,
+}
+
 impl<'a> From<InputRaw<'a>> for Data<'a> {
     fn from(value: InputRaw<'a>) -> Self {
         match value {
@@ -1,20 +1,36 @@
 use super::{
     data::{
-        About, BookOfAnswers, Data, Dice, DrawClowcard, Error, RandomPick, RelationshipCalculator,
+        About, Admin, AdminAction, Ask, Autocomplete, Birthday, BirthdayAction, Book, BookAction,
+        BookOfAnswers, Config, ConfigAction, Data, Deck, DeckAction, Dice, DrawClowcard, Error,
+        Help, Horoscope, IChing, Numerology, RandomPick, RelationshipCalculator, RuneInfo, Runes,
+        Upload,
     },
     InputRaw,
 };
 use crate::{
-    commands::Marker, handler::data::ClowCardInfo, models::custom_id::CustomId,
+    commands::Marker,
     extensions::CommandOptionValueData,
+    handler::data::ClowCardInfo,
+    models::{
+        blocklist::BlockTarget,
+        book_of_answers::{Language, ToneDistribution},
+        clow_cards::DEFAULT_DECK,
+        command_toggles::Toggle,
+        custom_id::CustomId,
+        zodiac::BirthDate,
+    },
 };
+use std::borrow::Cow;
 use twilight_model::{
     application::interaction::{
         application_command::{CommandData, CommandDataOption, CommandOptionValue},
-        Interaction, InteractionData,
+        Interaction, InteractionData, InteractionType,
     },
     gateway::payload::incoming::InteractionCreate,
-    id::{marker::UserMarker, Id},
+    id::{
+        marker::{ChannelMarker, GuildMarker, UserMarker},
+        Id,
+    },
 };
 
 impl<'a> From<&'a InteractionCreate> for InputRaw<'a> {
@@ -29,9 +45,30 @@ impl<'a> From<&'a Interaction> for InputRaw<'a> {
     }
 }
 
+/// Autocomplete carries the name of the command being typed,
+/// buttons belong to the command that sent them
+pub fn marker(value: &Interaction) -> Option<Marker> {
+    match value.data.as_ref()? {
+        InteractionData::ApplicationCommand(data) => data.name.parse().ok(),
+        InteractionData::MessageComponent(data) => match CustomId::from(data.custom_id.as_str()) {
+            CustomId::ButtonClowcardInfo(_) | CustomId::ButtonCardInfo(..) => {
+                Some(Marker::DrawClowcard)
+            }
+            CustomId::ButtonRuneInfo(..) => Some(Marker::Runes),
+        },
+        _ => None,
+    }
+}
+
 impl<'a> From<&'a Interaction> for Data<'a> {
     fn from(value: &'a Interaction) -> Self {
-        fn app_cmd(data: &CommandData, author: Id<UserMarker>) -> Data {
+        fn app_cmd(
+            data: &CommandData,
+            author: Id<UserMarker>,
+            guild: Option<Id<GuildMarker>>,
+            channel: Option<Id<ChannelMarker>>,
+            locale: Option<Language>,
+        ) -> Data<'_> {
             let args = data.options.as_slice();
             let Ok(name) = data.name.parse() else {
                 return Data::None;
@@ -41,8 +78,8 @@ impl<'a> From<&'a Interaction> for Data<'a> {
                 Marker::RandomPick => Data::RandomPick(args.into()),
                 Marker::DrawClowcard => {
                     let mut dc: DrawClowcard = args.into();
-                    dc.amount = dc.amount.map(|a| a.min(5));
                     dc.author = Some(author);
+                    dc.guild = guild;
                     Data::DrawClowcard(dc)
                 }
                 Marker::RelationshipCalculator => {
@@ -55,16 +92,126 @@ impl<'a> From<&'a Interaction> for Data<'a> {
                 Marker::BookOfAnswers => {
                     let mut boa: BookOfAnswers = args.into();
                     boa.author = Some(author);
+                    boa.guild = guild;
+                    // Answer in the user's language unless asked otherwise
+                    if !args.iter().any(|op| op.name == "language") {
+                        boa.language = locale;
+                    }
                     Data::BookOfAnswers(boa)
                 }
                 Marker::Dice => Data::Dice(args.into()),
+                Marker::Birthday => match Birthday::try_from(args) {
+                    Ok(mut bd) => {
+                        bd.author = Some(author);
+                        Data::Birthday(bd)
+                    }
+                    Err(err) => Data::Error(err),
+                },
+                Marker::Horoscope => {
+                    let mut hs: Horoscope = args.into();
+                    hs.author = Some(author);
+                    Data::Horoscope(hs)
+                }
+                Marker::IChing => {
+                    let mut ic: IChing = args.into();
+                    ic.author = Some(author);
+                    Data::IChing(ic)
+                }
+                Marker::Runes => {
+                    let mut rn: Runes = args.into();
+                    rn.author = Some(author);
+                    Data::Runes(rn)
+                }
+                Marker::Numerology => {
+                    let Some(CommandDataOption {
+                        name,
+                        value: CommandOptionValue::SubCommand(options),
+                    }) = args.first()
+                    else {
+                        unreachable!("Numerology takes a subcommand")
+                    };
+                    match name.as_str() {
+                        "compare" => {
+                            let mut lc: RelationshipCalculator = options.as_slice().into();
+                            let left = lc.targets.remaining_capacity();
+                            (0..left).for_each(|_| lc.targets.push(author));
+                            lc.targets.sort();
+                            lc.numerology = true;
+                            Data::LoveCalculator(lc)
+                        }
+                        _ => match Numerology::try_from(options.as_slice()) {
+                            Ok(nm) => Data::Numerology(nm),
+                            Err(err) => Data::Error(err),
+                        },
+                    }
+                }
+                Marker::Ask => {
+                    let mut ask: Ask = args.into();
+                    ask.author = Some(author);
+                    ask.guild = guild;
+                    ask.language = locale;
+                    Data::Ask(ask)
+                }
+                Marker::Config => {
+                    let mut config: Config = args.into();
+                    config.guild = guild;
+                    Data::Config(config)
+                }
+                Marker::Book => {
+                    let mut book: Book = args.into();
+                    book.guild = guild;
+                    if let BookAction::Upload(upload) = &mut book.action {
+                        // `file` is the only attachment option
+                        upload.attachment = data
+                            .resolved
+                            .as_ref()
+                            .and_then(|resolved| resolved.attachments.values().next());
+                    }
+                    Data::Book(book)
+                }
+                Marker::Deck => {
+                    let mut deck: Deck = args.into();
+                    deck.guild = guild;
+                    if let DeckAction::Upload(upload) = &mut deck.action {
+                        // `file` is the only attachment option
+                        upload.attachment = data
+                            .resolved
+                            .as_ref()
+                            .and_then(|resolved| resolved.attachments.values().next());
+                    }
+                    Data::Deck(deck)
+                }
                 Marker::About => Data::About(args.into()),
+                Marker::Help => Data::Help(Help { guild, channel }),
+                Marker::Admin => Data::Admin(Admin {
+                    author,
+                    action: args.into(),
+                }),
             }
         }
 
-        fn msg_com(cid: CustomId) -> Data {
+        fn focused(options: &[CommandDataOption]) -> Option<(&str, &str)> {
+            options.iter().find_map(|op| match &op.value {
+                CommandOptionValue::Focused(input, _) => Some((op.name.as_str(), input.as_str())),
+                CommandOptionValue::SubCommand(options) => focused(options),
+                _ => None,
+            })
+        }
+
+        fn msg_com(cid: CustomId, guild: Option<Id<GuildMarker>>) -> Data {
             match cid {
-                CustomId::ButtonClowcardInfo(name) => Data::ClowCardInfo(ClowCardInfo { name }),
+                // Buttons sent before decks existed
+                CustomId::ButtonClowcardInfo(name) => Data::ClowCardInfo(ClowCardInfo {
+                    guild,
+                    deck: Cow::Borrowed(DEFAULT_DECK),
+                    name,
+                }),
+                CustomId::ButtonCardInfo(deck, name) => {
+                    Data::ClowCardInfo(ClowCardInfo { guild, deck, name })
+                }
+                CustomId::ButtonRuneInfo(name, reversed) => {
+                    Data::RuneInfo(RuneInfo { name, reversed })
+                }
             }
         }
 
@@ -76,10 +223,24 @@ impl<'a> From<&'a Interaction> for Data<'a> {
         let author = value.author_id().expect("command should come from a user");
 
         match data {
-            InteractionData::ApplicationCommand(data) => app_cmd(data, author),
+            InteractionData::ApplicationCommand(data)
+                if value.kind == InteractionType::ApplicationCommandAutocomplete =>
+            {
+                let (option, input) = focused(&data.options).unwrap_or_default();
+                Self::Autocomplete(Autocomplete {
+                    guild: value.guild_id,
+                    option,
+                    input,
+                })
+            }
+            InteractionData::ApplicationCommand(data) => {
+                let locale = value.locale.as_deref().and_then(Language::from_locale);
+                let channel = value.channel.as_ref().map(|channel| channel.id);
+                app_cmd(data, author, value.guild_id, channel, locale)
+            }
             InteractionData::MessageComponent(data) => {
                 let cid = CustomId::from(data.custom_id.as_str());
-                msg_com(cid)
+                msg_com(cid, value.guild_id)
             }
             _ => Self::Error(Error {
                 error: "unsupported command type".to_owned(),
@@ -106,13 +267,24 @@ impl<'a> From<&'a [CommandDataOption]> for RandomPick<'a> {
 
 impl<'a> From<&'a [CommandDataOption]> for BookOfAnswers<'a> {
     fn from(value: &'a [CommandDataOption]) -> Self {
-        let input = value.first().map(|op| match &op.value {
-            CommandOptionValue::String(input) => input.as_str(),
-            _ => unreachable!("BookOfAnswers takes a string only"),
+        let mut prompt = None;
+        let mut category = None;
+        let mut book = None;
+        let mut language = None;
+        value.iter().for_each(|op| match op.name.as_str() {
+            "prompt" => prompt = op.value.string(),
+            "category" => category = op.value.string(),
+            "book" => book = op.value.string(),
+            "language" => language = op.value.string().and_then(Language::from_locale),
+            _ => {}
         });
         Self {
-            prompt: input,
+            prompt,
             author: None,
+            category,
+            guild: None,
+            book,
+            language,
             show_prompt: true,
         }
     }
@@ -122,15 +294,19 @@ impl<'a> From<&'a [CommandDataOption]> for DrawClowcard<'a> {
     fn from(value: &'a [CommandDataOption]) -> Self {
         let mut prompt = None;
         let mut amount = None;
+        let mut deck = None;
         value.iter().for_each(|op| match op.name.as_str() {
             "prompt" => prompt = op.value.string(),
             "amount" => amount = op.value.usize(),
+            "deck" => deck = op.value.string(),
             _ => {}
         });
         Self {
             prompt,
             amount,
             author: None,
+            guild: None,
+            deck,
             show_prompt: true,
         }
     }
@@ -147,7 +323,310 @@ impl<'a> From<&'a [CommandDataOption]> for RelationshipCalculator {
             .take(2)
             .collect();
 
-        Self { targets }
+        Self {
+            targets,
+            numerology: false,
+        }
+    }
+}
+
+impl TryFrom<&[CommandDataOption]> for Birthday {
+    type Error = Error;
+
+    fn try_from(value: &[CommandDataOption]) -> Result<Self, Self::Error> {
+        let Some(CommandDataOption {
+            name,
+            value: CommandOptionValue::SubCommand(options),
+        }) = value.first()
+        else {
+            unreachable!("Birthday takes a subcommand")
+        };
+
+        let action = match name.as_str() {
+            "set" => {
+                let mut day = 0;
+                let mut month = 0;
+                let mut year = None;
+                options.iter().for_each(|op| match op.name.as_str() {
+                    "day" => day = op.value.i64().unwrap_or_default(),
+                    "month" => month = op.value.i64().unwrap_or_default(),
+                    "year" => year = op.value.i64(),
+                    _ => {}
+                });
+                let date = u8::try_from(day)
+                    .ok()
+                    .zip(u8::try_from(month).ok())
+                    .and_then(|(day, month)| BirthDate::new(day, month))
+                    .and_then(|date| match year {
+                        Some(year) => date.with_year(u16::try_from(year).ok()?),
+                        None => Some(date),
+                    })
+                    .ok_or_else(|| Error {
+                        error: format!("Ngày {day}/{month} không hợp lệ"),
+                    })?;
+                BirthdayAction::Set(date)
+            }
+            "remove" => BirthdayAction::Remove,
+            _ => BirthdayAction::Show,
+        };
+
+        Ok(Self {
+            author: None,
+            action,
+        })
+    }
+}
+
+impl From<&[CommandDataOption]> for Horoscope {
+    fn from(value: &[CommandDataOption]) -> Self {
+        let target = value.first().map(|op| match &op.value {
+            CommandOptionValue::User(user) => *user,
+            _ => unreachable!("Horoscope takes a user only"),
+        });
+
+        Self {
+            target,
+            author: None,
+        }
+    }
+}
+
+impl<'a> From<&'a [CommandDataOption]> for IChing<'a> {
+    fn from(value: &'a [CommandDataOption]) -> Self {
+        let prompt = value.first().and_then(|op| op.value.string());
+        Self {
+            prompt,
+            author: None,
+            show_prompt: true,
+        }
+    }
+}
+
+impl<'a> From<&'a [CommandDataOption]> for Runes<'a> {
+    fn from(value: &'a [CommandDataOption]) -> Self {
+        let mut prompt = None;
+        let mut amount = None;
+        let mut norns = false;
+        value.iter().for_each(|op| match op.name.as_str() {
+            "prompt" => prompt = op.value.string(),
+            "count" => amount = op.value.usize(),
+            "norns" => norns = op.value.bool().unwrap_or_default(),
+            _ => {}
+        });
+        Self {
+            prompt,
+            author: None,
+            amount,
+            norns,
+            show_prompt: true,
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [CommandDataOption]> for Numerology<'a> {
+    type Error = Error;
+
+    fn try_from(value: &'a [CommandDataOption]) -> Result<Self, Self::Error> {
+        let mut name = "";
+        let mut date = "";
+        value.iter().for_each(|op| match op.name.as_str() {
+            "name" => name = op.value.string().unwrap_or_default(),
+            "date" => date = op.value.string().unwrap_or_default(),
+            _ => {}
+        });
+        let date = date
+            .parse::<BirthDate>()
+            .ok()
+            .filter(|date| date.year().is_some())
+            .ok_or_else(|| Error {
+                error: format!("Ngày sinh `{date}` không hợp lệ, hãy nhập theo dạng dd/mm/yyyy"),
+            })?;
+
+        Ok(Self { name, date })
+    }
+}
+
+impl<'a> From<&'a [CommandDataOption]> for Ask<'a> {
+    fn from(value: &'a [CommandDataOption]) -> Self {
+        let prompt = value.first().and_then(|op| op.value.string());
+        Self {
+            prompt,
+            author: None,
+            guild: None,
+            language: None,
+            show_prompt: true,
+        }
+    }
+}
+
+impl<'a> From<&'a [CommandDataOption]> for AdminAction<'a> {
+    fn from(value: &'a [CommandDataOption]) -> Self {
+        let Some(CommandDataOption { name, value }) = value.first() else {
+            unreachable!("Admin takes a subcommand")
+        };
+        let (name, options) = match value {
+            // `blocklist add` and the like
+            CommandOptionValue::SubCommandGroup(group) => match group.first() {
+                Some(CommandDataOption {
+                    name: sub,
+                    value: CommandOptionValue::SubCommand(options),
+                }) => (format!("{name} {sub}"), options.as_slice()),
+                _ => unreachable!("subcommand group takes a subcommand"),
+            },
+            CommandOptionValue::SubCommand(options) => (name.clone(), options.as_slice()),
+            _ => unreachable!("Admin takes a subcommand"),
+        };
+        let string = |key: &str| {
+            options
+                .iter()
+                .find(|op| op.name == key)
+                .and_then(|op| op.value.string())
+        };
+        let target = || {
+            let id = string("id")?.trim().parse().ok()?;
+            match string("kind")? {
+                "user" => Some(BlockTarget::User(id)),
+                "guild" => Some(BlockTarget::Guild(id.cast())),
+                _ => None,
+            }
+        };
+
+        match name.as_str() {
+            "stats" => Self::Stats,
+            "reload" => Self::Reload,
+            "announce" => Self::Announce {
+                message: string("message").unwrap_or_default(),
+                channel: string("channel").and_then(|id| id.trim().parse().ok()),
+            },
+            "blocklist add" => Self::BlocklistAdd(target()),
+            "blocklist remove" => Self::BlocklistRemove(target()),
+            "blocklist list" => Self::BlocklistList,
+            "shutdown" => Self::Shutdown,
+            _ => unreachable!("unknown admin subcommand"),
+        }
+    }
+}
+
+impl From<&[CommandDataOption]> for Config {
+    fn from(value: &[CommandDataOption]) -> Self {
+        let Some(CommandDataOption {
+            name,
+            value: CommandOptionValue::SubCommand(options),
+        }) = value.first()
+        else {
+            unreachable!("Config takes a subcommand")
+        };
+
+        if name == "commands" {
+            let mut command = None;
+            let mut toggle = None;
+            let mut channel = None;
+            options
+                .iter()
+                .for_each(|op| match (op.name.as_str(), &op.value) {
+                    ("command", CommandOptionValue::String(name)) => command = name.parse().ok(),
+                    ("state", CommandOptionValue::String(state)) => {
+                        toggle = match state.as_str() {
+                            "on" => Some(Toggle::On),
+                            "off" => Some(Toggle::Off),
+                            "inherit" => Some(Toggle::Inherit),
+                            _ => None,
+                        }
+                    }
+                    ("channel", CommandOptionValue::Channel(id)) => channel = Some(*id),
+                    _ => {}
+                });
+            return Self {
+                guild: None,
+                action: ConfigAction::Commands {
+                    command,
+                    toggle,
+                    channel,
+                },
+            };
+        }
+
+        let mut tones = ToneDistribution::default();
+        options.iter().for_each(|op| {
+            let weight = op.value.i64().unwrap_or_default().clamp(0, 100) as u8;
+            match op.name.as_str() {
+                "positive" => tones.positive = weight,
+                "neutral" => tones.neutral = weight,
+                "negative" => tones.negative = weight,
+                _ => {}
+            }
+        });
+
+        Self {
+            guild: None,
+            action: ConfigAction::OracleTone(tones),
+        }
+    }
+}
+
+impl<'a> From<&'a [CommandDataOption]> for Book<'a> {
+    fn from(value: &'a [CommandDataOption]) -> Self {
+        let Some(CommandDataOption {
+            name,
+            value: CommandOptionValue::SubCommand(options),
+        }) = value.first()
+        else {
+            unreachable!("Book takes a subcommand")
+        };
+        let string = |key: &str| {
+            options
+                .iter()
+                .find(|op| op.name == key)
+                .and_then(|op| op.value.string())
+                .unwrap_or_default()
+        };
+
+        let action = match name.as_str() {
+            "upload" => BookAction::Upload(Upload {
+                name: string("name").trim(),
+                attachment: None,
+                content: None,
+            }),
+            "remove" => BookAction::Remove(string("book")),
+            _ => BookAction::List,
+        };
+        Self {
+            guild: None,
+            action,
+        }
+    }
+}
+
+impl<'a> From<&'a [CommandDataOption]> for Deck<'a> {
+    fn from(value: &'a [CommandDataOption]) -> Self {
+        let Some(CommandDataOption {
+            name,
+            value: CommandOptionValue::SubCommand(options),
+        }) = value.first()
+        else {
+            unreachable!("Deck takes a subcommand")
+        };
+        let string = |key: &str| {
+            options
+                .iter()
+                .find(|op| op.name == key)
+                .and_then(|op| op.value.string())
+                .unwrap_or_default()
+        };
+
+        let action = match name.as_str() {
+            "upload" => DeckAction::Upload(Upload {
+                name: string("name").trim(),
+                attachment: None,
+                content: None,
+            }),
+            "remove" => DeckAction::Remove(string("deck")),
+            _ => DeckAction::List,
+        };
+        Self {
+            guild: None,
+            action,
+        }
     }
 }
 
@@ -1,8 +1,14 @@
 use super::{
-    data::{About, BookOfAnswers, Data, Dice, DrawClowcard, RandomPick, RelationshipCalculator},
+    data::{
+        About, Ask, Birthday, BirthdayAction, BookOfAnswers, Data, Dice, DrawClowcard, Help,
+        Horoscope, IChing, Numerology, RandomPick, RelationshipCalculator, Runes,
+    },
     InputRaw,
 };
-use crate::commands::Marker;
+use crate::{
+    commands::Marker,
+    models::{clow_cards::BUILTIN_DECKS, zodiac::BirthDate},
+};
 use std::ops::Not;
 use twilight_model::{channel::Message, gateway::payload::incoming::MessageCreate};
 
@@ -20,20 +26,27 @@ impl<'a> From<&'a Message> for InputRaw<'a> {
     }
 }
 
+/// Split `~cmd args` into the command name and its arguments
+fn split_command(content: &str) -> Option<(&str, &str)> {
+    let content = content.strip_prefix('~').map(str::trim_start)?;
+    Some(
+        content
+            .split_once(' ')
+            .map(|(cmd, args)| (cmd, args.trim_start()))
+            .unwrap_or((content, "")),
+    )
+}
+
+pub fn marker(value: &Message) -> Option<Marker> {
+    let (cmd, _) = split_command(&value.content)?;
+    cmd.parse().ok()
+}
+
 impl<'a> From<&'a Message> for Data<'a> {
     fn from(value: &'a Message) -> Self {
-        let Some(content) = value
-            .content
-            .as_str()
-            .strip_prefix('~')
-            .map(str::trim_start)
-        else {
+        let Some((cmd, args)) = split_command(&value.content) else {
             return Data::None;
         };
-        let (cmd, args) = content
-            .split_once(' ')
-            .map(|(cmd, args)| (cmd, args.trim_start()))
-            .unwrap_or((content, ""));
         let author = value.author.id;
 
         let Ok(name) = cmd.parse() else {
@@ -45,8 +58,8 @@ impl<'a> From<&'a Message> for Data<'a> {
                 Marker::RandomPick => Data::RandomPick(args.try_into()?),
                 Marker::DrawClowcard => {
                     let mut dc: DrawClowcard = args.into();
-                    dc.amount = dc.amount.map(|a| a.min(5));
                     dc.author = Some(author);
+                    dc.guild = value.guild_id;
                     Data::DrawClowcard(dc)
                 }
                 Marker::RelationshipCalculator => {
@@ -62,7 +75,50 @@ impl<'a> From<&'a Message> for Data<'a> {
                     Data::BookOfAnswers(boa)
                 }
                 Marker::Dice => Data::Dice(args.into()),
+                Marker::Birthday => {
+                    let mut bd: Birthday = args.try_into()?;
+                    bd.author = Some(author);
+                    Data::Birthday(bd)
+                }
+                Marker::Horoscope => {
+                    let mut hs: Horoscope = args.into();
+                    hs.author = Some(author);
+                    Data::Horoscope(hs)
+                }
+                Marker::IChing => {
+                    let mut ic: IChing = args.into();
+                    ic.author = Some(author);
+                    Data::IChing(ic)
+                }
+                Marker::Runes => {
+                    let mut rn: Runes = args.into();
+                    rn.author = Some(author);
+                    Data::Runes(rn)
+                }
+                Marker::Numerology => match args.strip_prefix("compare") {
+                    Some(args) => {
+                        let mut lc: RelationshipCalculator = args.into();
+                        let left = lc.targets.remaining_capacity();
+                        (0..left).for_each(|_| lc.targets.push(author));
+                        lc.targets.sort();
+                        lc.numerology = true;
+                        Data::LoveCalculator(lc)
+                    }
+                    None => Data::Numerology(args.try_into()?),
+                },
+                Marker::Ask => {
+                    let mut ask: Ask = args.into();
+                    ask.author = Some(author);
+                    ask.guild = value.guild_id;
+                    Data::Ask(ask)
+                }
+                // Server settings are only available through slash commands
+                Marker::Config | Marker::Book | Marker::Deck | Marker::Admin => Data::None,
                 Marker::About => Data::About(args.into()),
+                Marker::Help => Data::Help(Help {
+                    guild: value.guild_id,
+                    channel: Some(value.channel_id),
+                }),
             })
         })()
         .unwrap_or(Data::None)
@@ -92,6 +148,10 @@ impl<'a> TryFrom<&'a str> for BookOfAnswers<'a> {
         Ok(Self {
             prompt: input,
             author: None,
+            category: None,
+            guild: None,
+            book: None,
+            language: None,
             show_prompt: false,
         })
     }
@@ -99,6 +159,13 @@ impl<'a> TryFrom<&'a str> for BookOfAnswers<'a> {
 
 impl<'a> From<&'a str> for DrawClowcard<'a> {
     fn from(value: &'a str) -> Self {
+        // Optional built-in deck id first: `~dc rws-major 3 prompt`
+        let (deck, value) = match value.split_once(' ').unwrap_or((value, "")) {
+            (deck, rest) if BUILTIN_DECKS.iter().any(|builtin| builtin.id == deck) => {
+                (Some(deck), rest.trim_start())
+            }
+            _ => (None, value),
+        };
         let mut splitting = value.splitn(2, ' ');
         let amount = splitting
             .next()
@@ -113,6 +180,8 @@ impl<'a> From<&'a str> for DrawClowcard<'a> {
             prompt,
             amount,
             author: None,
+            guild: None,
+            deck,
             show_prompt: false,
         }
     }
@@ -127,7 +196,113 @@ impl<'a> From<&'a str> for RelationshipCalculator {
             .take(2)
             .collect();
 
-        Self { targets }
+        Self {
+            targets,
+            numerology: false,
+        }
+    }
+}
+
+impl TryFrom<&str> for Birthday {
+    type Error = ParseCommandError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (action, args) = value
+            .split_once(' ')
+            .map(|(action, args)| (action, args.trim()))
+            .unwrap_or((value, ""));
+        let action = match action {
+            "set" => BirthdayAction::Set(args.parse().map_err(|_| ParseCommandError)?),
+            "remove" => BirthdayAction::Remove,
+            "" | "show" => BirthdayAction::Show,
+            _ => return Err(ParseCommandError),
+        };
+        Ok(Self {
+            author: None,
+            action,
+        })
+    }
+}
+
+impl From<&str> for Horoscope {
+    fn from(value: &str) -> Self {
+        let target = value
+            .split_ascii_whitespace()
+            .map(|s| s.trim_matches(|c: char| !c.is_ascii_digit()))
+            .find_map(|s| s.parse().ok());
+
+        Self {
+            target,
+            author: None,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for IChing<'a> {
+    fn from(value: &'a str) -> Self {
+        Self {
+            prompt: value.is_empty().not().then_some(value),
+            author: None,
+            show_prompt: false,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Runes<'a> {
+    fn from(value: &'a str) -> Self {
+        let (first, rest) = value
+            .split_once(' ')
+            .map(|(first, rest)| (first, rest.trim_start()))
+            .unwrap_or((value, ""));
+        let norns = first.eq_ignore_ascii_case("norns");
+        let amount = (!norns)
+            .then(|| first.parse().ok())
+            .flatten()
+            .filter(|amount| *amount != 0);
+        let prompt = if norns || amount.is_some() {
+            rest
+        } else {
+            value
+        };
+        Self {
+            prompt: prompt.is_empty().not().then_some(prompt),
+            author: None,
+            amount,
+            norns,
+            show_prompt: false,
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Numerology<'a> {
+    type Error = ParseCommandError;
+
+    /// `dd/mm/yyyy Full Name`
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let (date, name) = value.split_once(' ').ok_or(ParseCommandError)?;
+        let date = date
+            .parse::<BirthDate>()
+            .ok()
+            .filter(|date| date.year().is_some())
+            .ok_or(ParseCommandError)?;
+        let name = name.trim();
+
+        name.is_empty()
+            .not()
+            .then_some(Self { name, date })
+            .ok_or(ParseCommandError)
+    }
+}
+
+impl<'a> From<&'a str> for Ask<'a> {
+    fn from(value: &'a str) -> Self {
+        Self {
+            prompt: value.is_empty().not().then_some(value),
+            author: None,
+            guild: None,
+            language: None,
+            show_prompt: false,
+        }
     }
 }
 
@@ -3,34 +3,231 @@ mod interaction;
 mod message;
 mod response_data;
 
-use data::Data;
+use data::{Book, BookAction, Data, Deck, DeckAction, Error};
 use response_data::ResponseData;
-use std::future::IntoFuture;
-use twilight_http::response::{marker::EmptyBody, ResponseFuture};
+use std::{
+    fmt::Write,
+    future::{Future, IntoFuture},
+    pin::Pin,
+    time::Duration,
+};
+use tracing::{info_span, warn, Span};
+use twilight_http::response::ResponseFuture;
 use twilight_model::{
-    application::interaction::Interaction, channel::Message, http::interaction::InteractionResponse,
+    application::interaction::{
+        application_command::{CommandDataOption, CommandOptionValue},
+        Interaction, InteractionData, InteractionType,
+    },
+    channel::{message::MessageFlags, Message},
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+    id::{
+        marker::{ChannelMarker, GuildMarker, UserMarker},
+        Id,
+    },
 };
 
-use crate::models::app_state::AppState;
+use crate::{commands::Marker, models::app_state::AppState};
 
 #[derive(Debug)]
 pub struct Handler<'a> {
     data: Data<'a>,
     raw: InputRaw<'a>,
     state: AppState,
+    /// An ephemeral "thinking" response was sent, the answer edits it
+    deferred: bool,
 }
 
+/// Sends the response, output is dropped since only errors matter
+pub type InteractionFuture = Pin<Box<dyn Future<Output = Result<(), twilight_http::Error>> + Send>>;
+
 #[derive(Debug, Clone, Copy)]
 pub enum InputRaw<'a> {
     Message(&'a Message),
     Interaction(&'a Interaction),
 }
 
+impl InputRaw<'_> {
+    /// The command invoked, `None` for messages that are not commands
+    pub fn marker(self) -> Option<Marker> {
+        match self {
+            InputRaw::Message(msg) => message::marker(msg),
+            InputRaw::Interaction(itr) => interaction::marker(itr),
+        }
+    }
+    pub fn user(self) -> Option<Id<UserMarker>> {
+        match self {
+            InputRaw::Message(msg) => Some(msg.author.id),
+            InputRaw::Interaction(itr) => itr.author_id(),
+        }
+    }
+    pub fn guild(self) -> Option<Id<GuildMarker>> {
+        match self {
+            InputRaw::Message(msg) => msg.guild_id,
+            InputRaw::Interaction(itr) => itr.guild_id,
+        }
+    }
+    pub fn channel(self) -> Option<Id<ChannelMarker>> {
+        match self {
+            InputRaw::Message(msg) => Some(msg.channel_id),
+            InputRaw::Interaction(itr) => itr.channel.as_ref().map(|channel| channel.id),
+        }
+    }
+    /// Guild administrator, only known for interactions
+    pub fn is_admin(self) -> bool {
+        match self {
+            InputRaw::Message(_) => false,
+            InputRaw::Interaction(itr) => itr
+                .member
+                .as_ref()
+                .and_then(|member| member.permissions)
+                .is_some_and(|permissions| permissions.contains(Permissions::ADMINISTRATOR)),
+        }
+    }
+    /// Span for everything logged while handling the event
+    pub fn span(self) -> Span {
+        let command = self.marker().map(Marker::name);
+        match self {
+            InputRaw::Message(msg) => info_span!(
+                "message",
+                id = msg.id.get(),
+                guild = msg.guild_id.map(Id::get),
+                channel = msg.channel_id.get(),
+                user = msg.author.id.get(),
+                command,
+            ),
+            InputRaw::Interaction(itr) => info_span!(
+                "interaction",
+                id = itr.id.get(),
+                guild = itr.guild_id.map(Id::get),
+                channel = itr.channel.as_ref().map(|channel| channel.id.get()),
+                user = itr.author_id().map(Id::get),
+                command,
+                path = self.path(),
+            ),
+        }
+    }
+    /// What the user sent, for error reports
+    pub fn input(self) -> String {
+        fn write_options(options: &[CommandDataOption], out: &mut String) {
+            for op in options {
+                let _ = match &op.value {
+                    CommandOptionValue::SubCommand(nested)
+                    | CommandOptionValue::SubCommandGroup(nested) => {
+                        let _ = write!(out, " {}", op.name);
+                        write_options(nested, out);
+                        continue;
+                    }
+                    CommandOptionValue::String(value) | CommandOptionValue::Focused(value, _) => {
+                        write!(out, " {}:{value}", op.name)
+                    }
+                    CommandOptionValue::Integer(value) => write!(out, " {}:{value}", op.name),
+                    CommandOptionValue::Number(value) => write!(out, " {}:{value}", op.name),
+                    CommandOptionValue::Boolean(value) => write!(out, " {}:{value}", op.name),
+                    CommandOptionValue::User(id) => write!(out, " {}:{id}", op.name),
+                    CommandOptionValue::Attachment(id) => write!(out, " {}:{id}", op.name),
+                    _ => write!(out, " {}:…", op.name),
+                };
+            }
+        }
+        match self {
+            InputRaw::Message(msg) => msg.content.clone(),
+            InputRaw::Interaction(itr) => match &itr.data {
+                Some(InteractionData::ApplicationCommand(data)) => {
+                    let mut out = format!("/{}", data.name);
+                    write_options(&data.options, &mut out);
+                    out
+                }
+                Some(InteractionData::MessageComponent(data)) => data.custom_id.clone(),
+                _ => String::new(),
+            },
+        }
+    }
+    /// How the command was invoked, used as a metric label
+    pub fn path(self) -> &'static str {
+        match self {
+            InputRaw::Message(_) => "message",
+            InputRaw::Interaction(itr) => match itr.kind {
+                InteractionType::ApplicationCommandAutocomplete => "autocomplete",
+                InteractionType::MessageComponent => "component",
+                _ => "interaction",
+            },
+        }
+    }
+}
+
 impl<'a> Handler<'a> {
     pub fn new(state: AppState, raw: impl Into<InputRaw<'a>>) -> Self {
         let raw = raw.into();
         let data = Data::from(raw);
-        Self { data, raw, state }
+        Self {
+            data,
+            raw,
+            state,
+            deferred: false,
+        }
+    }
+    /// Answer with `error` instead of running the command
+    pub fn reject(state: AppState, raw: impl Into<InputRaw<'a>>, error: String) -> Self {
+        Self {
+            data: Data::Error(Error { error }),
+            raw: raw.into(),
+            state,
+            deferred: false,
+        }
+    }
+    /// Download what the response needs ahead of time, since building it is synchronous.
+    /// The interaction is deferred first so a slow download does not miss Discord's deadline.
+    pub async fn prefetch(&mut self) {
+        let upload = match &mut self.data {
+            Data::Book(Book {
+                action: BookAction::Upload(upload),
+                ..
+            })
+            | Data::Deck(Deck {
+                action: DeckAction::Upload(upload),
+                ..
+            }) => upload,
+            _ => return,
+        };
+        // Oversized files are rejected when responding
+        let Some(attachment) = upload
+            .attachment
+            .filter(|attachment| attachment.size <= self.state.config.limits.max_upload_size)
+        else {
+            return;
+        };
+        if let InputRaw::Interaction(itr) = self.raw {
+            let response = InteractionResponse {
+                kind: InteractionResponseType::DeferredChannelMessageWithSource,
+                data: Some(InteractionResponseData {
+                    flags: Some(MessageFlags::EPHEMERAL),
+                    ..Default::default()
+                }),
+            };
+            match self
+                .state
+                .bot
+                .interaction(itr.application_id)
+                .create_response(itr.id, &itr.token, &response)
+                .await
+            {
+                Ok(_) => self.deferred = true,
+                Err(err) => warn!(?err, "unable to defer interaction"),
+            }
+        }
+        let timeout = Duration::from_secs(self.state.config.limits.upload_timeout_secs);
+        let content = async {
+            let response = self
+                .state
+                .http
+                .get(&attachment.url)
+                .timeout(timeout)
+                .send()
+                .await?;
+            Ok(response.error_for_status()?.text().await?)
+        };
+        upload.content = Some(content.await);
     }
     pub fn response_message_future(self) -> Option<ResponseFuture<Message>> {
         let InputRaw::Message(msg) = self.raw else {
@@ -46,7 +243,7 @@ impl<'a> Handler<'a> {
             embeds,
             components,
             ..
-        } = self.data.into();
+        } = (self.data, &self.state).into();
 
         Some(
             self.state
@@ -61,26 +258,52 @@ impl<'a> Handler<'a> {
     }
     #[allow(unused)]
     pub fn response_interaction(self) -> InteractionResponse {
-        ResponseData::from(self.data).into()
+        ResponseData::from((self.data, &self.state)).into()
     }
-    pub fn response_interaction_future(self) -> Option<ResponseFuture<EmptyBody>> {
+    pub fn response_interaction_future(self) -> Option<InteractionFuture> {
         let InputRaw::Interaction(itr) = self.raw else {
             return None;
         };
-        let response = ResponseData::from(self.data).into();
         let Interaction {
             id: interaction_id,
             application_id,
             token: interaction_token,
             ..
         } = itr;
+        let (interaction_id, application_id) = (*interaction_id, *application_id);
+        let token = interaction_token.clone();
+        let deferred = self.deferred;
+        let state = self.state.clone();
+        let data = ResponseData::from((self.data, &self.state));
 
-        Some(
-            self.state
+        if deferred {
+            let ResponseData {
+                content,
+                embeds,
+                components,
+                ..
+            } = data;
+            let content = content.into_owned();
+            return Some(Box::pin(async move {
+                state
+                    .bot
+                    .interaction(application_id)
+                    .update_response(&token)
+                    .content(Some(&content))
+                    .embeds(Some(&embeds))
+                    .components(Some(&components))
+                    .await
+                    .map(drop)
+            }));
+        }
+        let response = InteractionResponse::from(data);
+        Some(Box::pin(async move {
+            state
                 .bot
-                .interaction(*application_id)
-                .create_response(*interaction_id, interaction_token, &response)
-                .into_future(),
-        )
+                .interaction(application_id)
+                .create_response(interaction_id, &token, &response)
+                .await
+                .map(drop)
+        }))
     }
 }
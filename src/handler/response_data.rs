@@ -1,28 +1,38 @@
 use super::data::{
-    About, BookOfAnswers, ClowCardInfo, Data, Dice, DrawClowcard, Error, RandomPick,
-    RelationshipCalculator,
+    About, Admin, AdminAction, Ask, Autocomplete, Birthday, BirthdayAction, Book, BookAction,
+    BookOfAnswers, ClowCardInfo, Config, ConfigAction, Data, Deck, DeckAction, Dice, DrawClowcard,
+    Error, Help, Horoscope, IChing, Numerology, RandomPick, RelationshipCalculator, RuneInfo,
+    Runes, Upload,
 };
 use crate::{
-    constants::{self, color},
+    commands::{CommandRegister, Marker},
+    constants::limit,
     models::{
-        clow_cards::ClowCardDeck,
+        app_state::AppState,
+        blocklist::BlockTarget,
+        clow_cards::{ClowCardDeck, BUILTIN_DECKS, DEFAULT_DECK},
+        command_toggles::{CommandToggles, Toggle},
+        data_registry,
         relationship_level::RelationshipLevel,
+        runes::RuneSet,
         seed_generator::{SeedGenerator, TimeHash},
     },
 };
-use rand::seq::SliceRandom;
+use rand::{seq::SliceRandom, Rng};
+use std::borrow::Cow;
 use std::fmt::Write;
-use std::{borrow::Cow, sync::LazyLock};
-use tracing::warn;
+use tracing::{info, warn};
 use twilight_mention::{
     timestamp::{Timestamp, TimestampStyle},
     Mention,
 };
 use twilight_model::{
+    application::command::{CommandOptionChoice, CommandOptionChoiceValue},
     channel::message::{Component, Embed, MessageFlags},
     http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+    id::{marker::ChannelMarker, Id},
 };
-use twilight_util::builder::embed::EmbedBuilder;
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
 
 #[derive(Default)]
 pub struct ResponseData<'a> {
@@ -30,20 +40,35 @@ pub struct ResponseData<'a> {
     pub embeds: Vec<Embed>,
     pub components: Vec<Component>,
     pub ephemeral: bool,
+    /// Answer an autocomplete interaction instead of sending a message
+    pub choices: Option<Vec<CommandOptionChoice>>,
 }
 
-impl From<Data<'_>> for ResponseData<'_> {
-    fn from(value: Data) -> Self {
+impl From<(Data<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Data, &AppState)) -> Self {
         match value {
             Data::RandomPick(inner) => inner.into(),
-            Data::BookOfAnswers(inner) => inner.into(),
-            Data::DrawClowcard(inner) => inner.into(),
-            Data::ClowCardInfo(inner) => inner.into(),
-            Data::Dice(inner) => inner.into(),
-            Data::LoveCalculator(inner) => inner.into(),
-            Data::About(inner) => inner.into(),
+            Data::BookOfAnswers(inner) => (inner, state).into(),
+            Data::DrawClowcard(inner) => (inner, state).into(),
+            Data::ClowCardInfo(inner) => (inner, state).into(),
+            Data::Dice(inner) => (inner, state).into(),
+            Data::LoveCalculator(inner) => (inner, state).into(),
+            Data::Birthday(inner) => (inner, state).into(),
+            Data::Horoscope(inner) => (inner, state).into(),
+            Data::IChing(inner) => (inner, state).into(),
+            Data::Runes(inner) => (inner, state).into(),
+            Data::RuneInfo(inner) => inner.into(),
+            Data::Numerology(inner) => (inner, state).into(),
+            Data::Ask(inner) => (inner, state).into(),
+            Data::Config(inner) => (inner, state).into(),
+            Data::Book(inner) => (inner, state).into(),
+            Data::Deck(inner) => (inner, state).into(),
+            Data::Autocomplete(inner) => (inner, state).into(),
+            Data::About(inner) => (inner, state).into(),
+            Data::Help(inner) => (inner, state).into(),
+            Data::Admin(inner) => (inner, state).into(),
             Data::None => Default::default(),
-            Data::Error(inner) => inner.into(),
+            Data::Error(inner) => (inner, state).into(),
         }
     }
 }
@@ -98,18 +123,26 @@ impl From<RandomPick<'_>> for ResponseData<'_> {
     }
 }
 
-impl From<BookOfAnswers<'_>> for ResponseData<'_> {
-    fn from(value: BookOfAnswers) -> Self {
+impl From<(BookOfAnswers<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (BookOfAnswers, &AppState)) -> Self {
         use crate::models::book_of_answers::BookOfAnswers;
 
         let author = value.author.expect("author should always be present");
-        let quote = BookOfAnswers::draw(value.prompt, author);
+        let guild_quote = value.guild.zip(value.book).and_then(|(guild, book)| {
+            let data = state.storage.read();
+            let book = data.answer_books.get(&guild)?.get(book)?;
+            let answer = book.draw_from(value.prompt, author, value.category);
+            Some(answer.render(value.language))
+        });
+        let quote = guild_quote.unwrap_or_else(|| {
+            BookOfAnswers::draw(value.prompt, author, value.category, value.language)
+        });
 
-        let content = if value.prompt.is_some() && value.show_prompt {
-            let prompt = value.prompt.unwrap();
-            format!("**Prompt:** {prompt}\n>>> {quote}").into()
-        } else {
-            quote.into()
+        let content = match value.prompt {
+            Some(prompt) if value.show_prompt => {
+                format!("**Prompt:** {prompt}\n>>> {quote}").into()
+            }
+            _ => quote.into(),
         };
 
         Self {
@@ -119,22 +152,63 @@ impl From<BookOfAnswers<'_>> for ResponseData<'_> {
     }
 }
 
-impl From<DrawClowcard<'_>> for ResponseData<'_> {
-    fn from(value: DrawClowcard<'_>) -> Self {
+impl From<(DrawClowcard<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (DrawClowcard<'_>, &AppState)) -> Self {
         let DrawClowcard {
             prompt,
             author,
             amount,
+            guild,
+            deck,
             show_prompt,
         } = value;
         let author = author.expect("An author must be included in DrawClowcard");
+        let amount = amount.map(|amount| amount.min(usize::from(state.config.limits.max_clow)));
+
+        let deck_id = deck.unwrap_or(DEFAULT_DECK);
+        let drawn = match ClowCardDeck::builtin(deck_id) {
+            Some(builtin) => Some(builtin.draw(
+                &state.config,
+                &state.assets,
+                deck_id,
+                prompt,
+                author,
+                amount,
+            )),
+            None => guild.and_then(|guild| {
+                let data = state.storage.read();
+                let deck = data.card_decks.get(&guild)?.get(deck_id)?;
+                Some(deck.draw(
+                    &state.config,
+                    &state.assets,
+                    deck_id,
+                    prompt,
+                    author,
+                    amount,
+                ))
+            }),
+        };
+        let Some((embeds, components)) = drawn else {
+            return (
+                Error {
+                    error: format!("Không tìm thấy bộ bài `{deck_id}`"),
+                },
+                state,
+            )
+                .into();
+        };
+        let label = BUILTIN_DECKS
+            .iter()
+            .find(|builtin| builtin.id == deck_id)
+            .map_or(deck_id, |builtin| builtin.label);
 
         let content = if prompt.is_none() && amount.is_none() {
             // Daily
-            let unix = SeedGenerator::specific_time(TimeHash::Day);
+            let unix =
+                SeedGenerator::specific_time(TimeHash::Day(state.config.time.utc_offset_minutes));
             let next = Timestamp::new(unix, Some(TimestampStyle::ShortDate));
             format!(
-                "Thẻ bài Clow của {} hôm nay ({})",
+                "Thẻ bài {label} của {} hôm nay ({})",
                 author.mention(),
                 next.mention()
             )
@@ -148,8 +222,6 @@ impl From<DrawClowcard<'_>> for ResponseData<'_> {
         }
         .into();
 
-        let (embeds, components) = ClowCardDeck::draw(prompt, author, amount);
-
         Self {
             content,
             embeds,
@@ -158,15 +230,30 @@ impl From<DrawClowcard<'_>> for ResponseData<'_> {
         }
     }
 }
-impl From<ClowCardInfo<'_>> for ResponseData<'_> {
-    fn from(value: ClowCardInfo<'_>) -> Self {
-        let content = ClowCardDeck::long_by_name(&value.name)
-            .map(Cow::from)
-            .unwrap_or_default();
-
-        if content.is_empty() {
-            warn!("unknown ClowCard: `{}`", value.name);
-        }
+impl From<(ClowCardInfo<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (ClowCardInfo<'_>, &AppState)) -> Self {
+        let content = match ClowCardDeck::builtin(&value.deck) {
+            Some(builtin) => builtin
+                .long_by_name(&value.name)
+                .map(|long| Cow::Owned(long.to_owned())),
+            None => value.guild.and_then(|guild| {
+                let data = state.storage.read();
+                let deck = data.card_decks.get(&guild)?.get(value.deck.as_ref())?;
+                deck.long_by_name(&value.name)
+                    .map(|long| Cow::Owned(long.to_owned()))
+            }),
+        };
+        let Some(content) = content else {
+            // The guild deck may have been removed since
+            warn!("unknown card: `{}` in deck `{}`", value.name, value.deck);
+            return (
+                Error {
+                    error: "Không tìm thấy lá bài này".to_owned(),
+                },
+                state,
+            )
+                .into();
+        };
 
         Self {
             content,
@@ -175,14 +262,17 @@ impl From<ClowCardInfo<'_>> for ResponseData<'_> {
         }
     }
 }
-impl From<Dice> for ResponseData<'_> {
-    fn from(value: Dice) -> Self {
+impl From<(Dice, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Dice, &AppState)) -> Self {
+        const FACES: [&str; 6] = ["⚀", "⚁", "⚂", "⚃", "⚄", "⚅"];
+
         let mut rng = rand::thread_rng();
         let mut content = String::new();
-        for _ in 0..value.amount {
-            let die = constants::DICE
-                .choose(&mut rng)
-                .expect("DICE is always valid");
+        for _ in 0..value.amount.min(u32::from(state.config.limits.max_dice)) {
+            let face = rng.gen_range(0..FACES.len());
+            let die = state
+                .assets
+                .emoji_with(&format!("dice-{}", face + 1), FACES[face], &mut rng);
             let _ = write!(&mut content, "{die} ");
         }
         let content = content.into();
@@ -193,13 +283,46 @@ impl From<Dice> for ResponseData<'_> {
         }
     }
 }
-impl From<RelationshipCalculator> for ResponseData<'_> {
-    fn from(value: RelationshipCalculator) -> Self {
+impl From<(RelationshipCalculator, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (RelationshipCalculator, &AppState)) -> Self {
         let [user1, user2] = value
             .targets
             .into_inner()
             .expect("RelaCalc always find a way to extract 2 user ids");
-        let embeds = RelationshipLevel::embed(user1, user2);
+        use crate::models::numerology::Numerology;
+
+        let (zodiacs, life_paths) = {
+            let storage = state.storage.read();
+            let birthday = |user| storage.birthdays.get(&user).copied();
+            let (date1, date2) = (birthday(user1), birthday(user2));
+            let zodiacs = date1.map(|d| d.zodiac()).zip(date2.map(|d| d.zodiac()));
+            let life_paths = date1
+                .and_then(Numerology::life_path)
+                .zip(date2.and_then(Numerology::life_path));
+            (zodiacs, life_paths)
+        };
+        let life_paths = match (value.numerology, life_paths) {
+            (false, _) => None,
+            (true, Some(life_paths)) => Some(life_paths),
+            (true, None) => {
+                return (
+                    Error {
+                        error: "Cả 2 users cần lưu ngày sinh đầy đủ năm sinh bằng `/birthday set`"
+                            .to_owned(),
+                    },
+                    state,
+                )
+                    .into()
+            }
+        };
+        let embeds = RelationshipLevel::embed(
+            &state.config,
+            &state.assets,
+            user1,
+            user2,
+            zodiacs,
+            life_paths,
+        );
         let content = format!(
             "Mối quan hệ giữa {} và {} hiện đang là..",
             user1.mention(),
@@ -213,18 +336,845 @@ impl From<RelationshipCalculator> for ResponseData<'_> {
         }
     }
 }
-impl From<About> for ResponseData<'_> {
-    fn from(_value: About) -> Self {
-        static ABOUT: LazyLock<Box<str>> = LazyLock::new(|| {
-            std::fs::read_to_string("static/about.md")
-                .map(Into::into)
-                .unwrap_or_default()
-        });
+impl From<(Birthday, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Birthday, &AppState)) -> Self {
+        let author = value.author.expect("author should always be present");
+
+        let result = match value.action {
+            BirthdayAction::Set(date) => state
+                .storage
+                .update(|data| data.birthdays.insert(author, date))
+                .map(|_| format!("Đã lưu ngày sinh **{date}** của bạn ({})", date.zodiac())),
+            BirthdayAction::Remove => state
+                .storage
+                .update(|data| data.birthdays.remove(&author))
+                .map(|removed| match removed {
+                    Some(_) => "Đã xoá ngày sinh của bạn".to_owned(),
+                    None => "Bạn chưa lưu ngày sinh".to_owned(),
+                }),
+            BirthdayAction::Show => Ok(match state.storage.read().birthdays.get(&author) {
+                Some(date) => format!("Ngày sinh của bạn là **{date}** ({})", date.zodiac()),
+                None => "Bạn chưa lưu ngày sinh, hãy dùng `/birthday set`".to_owned(),
+            }),
+        };
+
+        match result {
+            Ok(content) => Self {
+                content: content.into(),
+                ephemeral: true,
+                ..Default::default()
+            },
+            Err(err) => {
+                warn!(?err, "unable to update birthday");
+                (
+                    Error {
+                        error: "Không thể lưu ngày sinh, vui lòng thử lại sau".to_owned(),
+                    },
+                    state,
+                )
+                    .into()
+            }
+        }
+    }
+}
+impl From<(Horoscope, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Horoscope, &AppState)) -> Self {
+        use crate::models::horoscope::Horoscope;
+
+        let author = value.author.expect("author should always be present");
+        let target = value.target.unwrap_or(author);
+        let Some(date) = state.storage.read().birthdays.get(&target).copied() else {
+            let error = if target == author {
+                "Bạn chưa lưu ngày sinh, hãy dùng `/birthday set` trước".to_owned()
+            } else {
+                format!("{} chưa lưu ngày sinh", target.mention())
+            };
+            return (Error { error }, state).into();
+        };
+
+        let unix =
+            SeedGenerator::specific_time(TimeHash::Day(state.config.time.utc_offset_minutes));
+        let today = Timestamp::new(unix, Some(TimestampStyle::ShortDate));
+        let content = format!(
+            "Tử vi hôm nay ({}) của {}",
+            today.mention(),
+            target.mention()
+        )
+        .into();
+        let embeds = vec![Horoscope::embed(&state.config, date.zodiac())];
+
+        Self {
+            content,
+            embeds,
+            ..Default::default()
+        }
+    }
+}
+impl From<(IChing<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (IChing<'_>, &AppState)) -> Self {
+        use crate::models::i_ching::IChing;
+
+        let prompt = value.prompt;
+        let author = value.author.expect("An author must be included in IChing");
+
+        let content = match prompt {
+            None => {
+                // Daily
+                let unix = SeedGenerator::specific_time(TimeHash::Day(
+                    state.config.time.utc_offset_minutes,
+                ));
+                let next = Timestamp::new(unix, Some(TimestampStyle::ShortDate));
+                format!(
+                    "Quẻ Kinh Dịch của {} hôm nay ({})",
+                    author.mention(),
+                    next.mention()
+                )
+            }
+            Some(prompt) if value.show_prompt => format!("**Prompt:** {prompt}"),
+            Some(_) => format!("{} vừa gieo một quẻ Kinh Dịch", author.mention()),
+        }
+        .into();
+
+        let embeds = IChing::cast(&state.config, prompt, author);
+
+        Self {
+            content,
+            embeds,
+            ..Default::default()
+        }
+    }
+}
+impl From<(Runes<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Runes<'_>, &AppState)) -> Self {
+        let Runes {
+            prompt,
+            author,
+            amount,
+            norns,
+            show_prompt,
+        } = value;
+        let author = author.expect("An author must be included in Runes");
+        let amount = amount.map(|amount| amount.min(usize::from(state.config.limits.max_runes)));
+
+        let content = if prompt.is_none() && amount.is_none() && !norns {
+            // Daily
+            let unix =
+                SeedGenerator::specific_time(TimeHash::Day(state.config.time.utc_offset_minutes));
+            let next = Timestamp::new(unix, Some(TimestampStyle::ShortDate));
+            format!(
+                "Đá rune của {} hôm nay ({})",
+                author.mention(),
+                next.mention()
+            )
+        } else if let (Some(prompt), true) = (prompt, show_prompt) {
+            format!("**Prompt:** {prompt}")
+        } else if norns {
+            format!("{} vừa trải bài Norns", author.mention())
+        } else {
+            let amount = amount.unwrap_or(1);
+            format!(
+                "{} vừa gieo ngẫu nhiên {} đá rune",
+                author.mention(),
+                amount
+            )
+        }
+        .into();
+
+        let (embeds, components) = RuneSet::draw(&state.config, prompt, author, amount, norns);
+
+        Self {
+            content,
+            embeds,
+            components,
+            ..Default::default()
+        }
+    }
+}
+impl From<RuneInfo<'_>> for ResponseData<'_> {
+    fn from(value: RuneInfo<'_>) -> Self {
+        let content = RuneSet::long_by_name(&value.name, value.reversed).unwrap_or_default();
+
+        if content.is_empty() {
+            warn!("unknown Rune: `{}`", value.name);
+        }
+
+        Self {
+            content,
+            ephemeral: true,
+            ..Default::default()
+        }
+    }
+}
+impl From<(Numerology<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Numerology<'_>, &AppState)) -> Self {
+        use crate::models::numerology::Numerology;
+
+        let embeds = vec![Numerology::embed(&state.config, value.name, value.date)];
+
+        Self {
+            embeds,
+            ..Default::default()
+        }
+    }
+}
+impl From<(Ask<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Ask<'_>, &AppState)) -> Self {
+        use crate::models::book_of_answers::BookOfAnswers;
+
+        let author = value.author.expect("author should always be present");
+        let tones = value
+            .guild
+            .and_then(|guild| state.storage.read().oracle_tones.get(&guild).copied())
+            .unwrap_or_default();
+        let answer = BookOfAnswers::ask(value.prompt, author, tones, value.language);
+
+        let content = match value.prompt {
+            Some(prompt) if value.show_prompt => {
+                format!("**Prompt:** {prompt}\n>>> 🎱 {answer}").into()
+            }
+            _ => format!("🎱 {answer}").into(),
+        };
+
+        Self {
+            content,
+            ..Default::default()
+        }
+    }
+}
+impl From<(Config, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Config, &AppState)) -> Self {
+        let Some(guild) = value.guild else {
+            return (
+                Error {
+                    error: "Lệnh này chỉ dùng được trong server".to_owned(),
+                },
+                state,
+            )
+                .into();
+        };
+
+        let result = match value.action {
+            ConfigAction::OracleTone(tones) if tones.is_empty() => {
+                return (
+                    Error {
+                        error: "Cần ít nhất một tỉ lệ lớn hơn 0".to_owned(),
+                    },
+                    state,
+                )
+                    .into()
+            }
+            ConfigAction::OracleTone(tones) => state
+                .storage
+                .update(|data| data.oracle_tones.insert(guild, tones))
+                .map(|_| {
+                    format!(
+                        "Đã cập nhật tỉ lệ câu trả lời của `/ask`: \
+                        tích cực {}, trung lập {}, tiêu cực {}",
+                        tones.positive, tones.neutral, tones.negative
+                    )
+                }),
+            ConfigAction::Commands { command: None, .. } => {
+                let storage = state.storage.read();
+                Ok(describe_toggles(storage.command_toggles.get(&guild)))
+            }
+            ConfigAction::Commands {
+                command: Some(marker),
+                toggle,
+                channel,
+            } => {
+                let Some(toggle) = toggle else {
+                    return (
+                        Error {
+                            error: "Hãy chọn trạng thái cho lệnh".to_owned(),
+                        },
+                        state,
+                    )
+                        .into();
+                };
+                if !marker.toggleable() {
+                    return (
+                        Error {
+                            error: format!("Không thể tắt `/{}`", marker.name()),
+                        },
+                        state,
+                    )
+                        .into();
+                }
+                let place = channel.map_or("server".to_owned(), |channel| {
+                    format!("kênh {}", channel.mention())
+                });
+                state
+                    .storage
+                    .update(|data| {
+                        let toggles = data.command_toggles.entry(guild).or_default();
+                        toggles.set(channel, marker, toggle);
+                        if toggles.is_empty() {
+                            data.command_toggles.remove(&guild);
+                        }
+                    })
+                    .map(|_| match (toggle, channel) {
+                        (Toggle::Off, _) => format!("Đã tắt `/{}` trong {place}", marker.name()),
+                        (Toggle::Inherit, Some(_)) => format!(
+                            "`/{}` trong {place} sẽ theo cài đặt của server",
+                            marker.name()
+                        ),
+                        _ => format!("Đã bật `/{}` trong {place}", marker.name()),
+                    })
+            }
+        };
+
+        match result {
+            Ok(content) => Self {
+                content: content.into(),
+                ephemeral: true,
+                ..Default::default()
+            },
+            Err(err) => {
+                warn!(?err, "unable to update guild config");
+                (
+                    Error {
+                        error: "Không thể lưu cài đặt, vui lòng thử lại sau".to_owned(),
+                    },
+                    state,
+                )
+                    .into()
+            }
+        }
+    }
+}
+fn describe_toggles(toggles: Option<&CommandToggles>) -> String {
+    let Some(toggles) = toggles.filter(|toggles| !toggles.is_empty()) else {
+        return "Tất cả các lệnh đều đang bật".to_owned();
+    };
+    let list = |names: &mut dyn Iterator<Item = &String>| {
+        names
+            .map(|name| format!("`/{name}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut content = String::new();
+    if !toggles.disabled.is_empty() {
+        let _ = writeln!(
+            content,
+            "Tắt trong server: {}",
+            list(&mut toggles.disabled.iter())
+        );
+    }
+    for (channel, overrides) in &toggles.channels {
+        let _ = write!(content, "{}:", channel.mention());
+        for (enabled, label) in [(true, "bật"), (false, "tắt")] {
+            let mut names = overrides
+                .iter()
+                .filter(|(_, value)| **value == enabled)
+                .map(|(name, _)| name)
+                .peekable();
+            if names.peek().is_some() {
+                let _ = write!(content, " {label} {}", list(&mut names));
+            }
+        }
+        content.push('\n');
+    }
+    content
+}
+impl From<(Help, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Help, &AppState)) -> Self {
+        let toggles = value
+            .guild
+            .and_then(|guild| state.storage.read().command_toggles.get(&guild).cloned());
+
+        let mut commands = CommandRegister::new(state.clone()).commands();
+        commands.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+        let mut description = String::new();
+        let mut hidden = 0;
+        for command in commands {
+            let Ok(marker) = command.name.parse::<Marker>() else {
+                continue;
+            };
+            // Server settings are only available through slash commands, in servers
+            let guild_only = matches!(marker, Marker::Config | Marker::Book | Marker::Deck);
+            if guild_only && value.guild.is_none() {
+                continue;
+            }
+            if toggles
+                .as_ref()
+                .is_some_and(|toggles| !toggles.is_enabled(value.channel, marker))
+            {
+                hidden += 1;
+                continue;
+            }
+            let _ = write!(description, "`/{}`", command.name);
+            if !guild_only {
+                marker.names().iter().for_each(|name| {
+                    let _ = write!(description, " `~{name}`");
+                });
+            }
+            let _ = writeln!(description, " — {}", command.description);
+        }
+
+        let mut embed = EmbedBuilder::new()
+            .title("Danh sách lệnh")
+            .description(description)
+            .color(state.config.colors.primary);
+        if hidden > 0 {
+            embed = embed.footer(EmbedFooterBuilder::new(format!(
+                "{hidden} lệnh đã bị tắt ở đây"
+            )));
+        }
+        Self {
+            embeds: vec![embed.build()],
+            ephemeral: true,
+            ..Default::default()
+        }
+    }
+}
+impl From<(Book<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Book<'_>, &AppState)) -> Self {
+        use crate::models::book_of_answers::BookOfAnswers;
+
+        let Some(guild) = value.guild else {
+            return (
+                Error {
+                    error: "Lệnh này chỉ dùng được trong server".to_owned(),
+                },
+                state,
+            )
+                .into();
+        };
+        let error = |error: String| -> Self { (Error { error }, state).into() };
+
+        let result = match value.action {
+            BookAction::Upload(upload) => {
+                let name = upload.name;
+                let (filename, raw) = match upload.read(state.config.limits.max_upload_size) {
+                    Ok(file) => file,
+                    Err(err) => return error(err),
+                };
+                let book = if filename.to_lowercase().ends_with(".json") {
+                    BookOfAnswers::from_json(&raw)
+                } else {
+                    BookOfAnswers::from_text(&raw)
+                };
+                let book = match book {
+                    Ok(book) => book,
+                    Err(err) => return error(format!("Sách không hợp lệ: {err}")),
+                };
+                let amount = book.len();
+                let saved = state.storage.update(|data| {
+                    let books = data.answer_books.entry(guild).or_default();
+                    let full = books.len() >= state.config.limits.max_guild_books;
+                    if full && !books.contains_key(name) {
+                        return false;
+                    }
+                    books.insert(name.to_owned(), book);
+                    true
+                });
+                saved.map(|saved| {
+                    if saved {
+                        Ok(format!("Đã lưu sách `{name}` với {amount} câu trả lời"))
+                    } else {
+                        Err(format!(
+                            "Server chỉ có thể có tối đa {} sách",
+                            state.config.limits.max_guild_books
+                        ))
+                    }
+                })
+            }
+            BookAction::Remove(name) => state
+                .storage
+                .update(|data| {
+                    let books = data.answer_books.get_mut(&guild)?;
+                    let removed = books.remove(name);
+                    if books.is_empty() {
+                        data.answer_books.remove(&guild);
+                    }
+                    removed
+                })
+                .map(|removed| match removed {
+                    Some(_) => Ok(format!("Đã xoá sách `{name}`")),
+                    None => Err(format!("Không tìm thấy sách `{name}`")),
+                }),
+            BookAction::List => {
+                let data = state.storage.read();
+                let content = match data.answer_books.get(&guild) {
+                    Some(books) => books.iter().fold(
+                        "**Sách của server:**".to_owned(),
+                        |mut content, (name, book)| {
+                            let _ = write!(content, "\n- `{name}`: {} câu trả lời", book.len());
+                            content
+                        },
+                    ),
+                    None => "Server chưa có sách nào, dùng `/book upload` để thêm".to_owned(),
+                };
+                Ok(Ok(content))
+            }
+        };
+
+        match result {
+            Ok(Ok(content)) => Self {
+                content: content.into(),
+                ephemeral: true,
+                ..Default::default()
+            },
+            Ok(Err(err)) => error(err),
+            Err(err) => {
+                warn!(?err, "unable to update guild answer books");
+                error("Không thể lưu sách, vui lòng thử lại sau".to_owned())
+            }
+        }
+    }
+}
+
+impl From<(Deck<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Deck<'_>, &AppState)) -> Self {
+        let Some(guild) = value.guild else {
+            return (
+                Error {
+                    error: "Lệnh này chỉ dùng được trong server".to_owned(),
+                },
+                state,
+            )
+                .into();
+        };
+        let error = |error: String| -> Self { (Error { error }, state).into() };
+
+        let result = match value.action {
+            DeckAction::Upload(upload) => {
+                let name = upload.name;
+                if ClowCardDeck::builtin(name).is_some() {
+                    return error(format!("`{name}` trùng tên với bộ bài có sẵn"));
+                }
+                // Deck ids are packed into button custom ids
+                if name.len() > limit::MAX_CARD_NAME {
+                    return error("Tên bộ bài quá dài".to_owned());
+                }
+                let raw = match upload.read(state.config.limits.max_upload_size) {
+                    Ok((_, raw)) => raw,
+                    Err(err) => return error(err),
+                };
+                let deck = match ClowCardDeck::from_json(&raw, "") {
+                    Ok(deck) => deck,
+                    Err(err) => return error(format!("Bộ bài không hợp lệ: {err}")),
+                };
+                let amount = deck.len();
+                let saved = state.storage.update(|data| {
+                    let decks = data.card_decks.entry(guild).or_default();
+                    let full = decks.len() >= state.config.limits.max_guild_decks;
+                    if full && !decks.contains_key(name) {
+                        return false;
+                    }
+                    decks.insert(name.to_owned(), deck);
+                    true
+                });
+                saved.map(|saved| {
+                    if saved {
+                        Ok(format!("Đã lưu bộ bài `{name}` với {amount} lá bài"))
+                    } else {
+                        Err(format!(
+                            "Server chỉ có thể có tối đa {} bộ bài",
+                            state.config.limits.max_guild_decks
+                        ))
+                    }
+                })
+            }
+            DeckAction::Remove(name) => state
+                .storage
+                .update(|data| {
+                    let decks = data.card_decks.get_mut(&guild)?;
+                    let removed = decks.remove(name);
+                    if decks.is_empty() {
+                        data.card_decks.remove(&guild);
+                    }
+                    removed
+                })
+                .map(|removed| match removed {
+                    Some(_) => Ok(format!("Đã xoá bộ bài `{name}`")),
+                    None => Err(format!("Không tìm thấy bộ bài `{name}`")),
+                }),
+            DeckAction::List => {
+                let data = state.storage.read();
+                let content = match data.card_decks.get(&guild) {
+                    Some(decks) => decks.iter().fold(
+                        "**Bộ bài của server:**".to_owned(),
+                        |mut content, (name, deck)| {
+                            let _ = write!(content, "\n- `{name}`: {} lá bài", deck.len());
+                            content
+                        },
+                    ),
+                    None => "Server chưa có bộ bài nào, dùng `/deck upload` để thêm".to_owned(),
+                };
+                Ok(Ok(content))
+            }
+        };
+
+        match result {
+            Ok(Ok(content)) => Self {
+                content: content.into(),
+                ephemeral: true,
+                ..Default::default()
+            },
+            Ok(Err(err)) => error(err),
+            Err(err) => {
+                warn!(?err, "unable to update guild card decks");
+                error("Không thể lưu bộ bài, vui lòng thử lại sau".to_owned())
+            }
+        }
+    }
+}
+
+impl Upload<'_> {
+    /// Filename and downloaded content, or a message for the user
+    fn read(self, max_size: u64) -> Result<(String, String), String> {
+        let Some(attachment) = self.attachment else {
+            return Err("Không tìm thấy tệp đính kèm".to_owned());
+        };
+        if self.name.is_empty() {
+            return Err("Tên không được để trống".to_owned());
+        }
+        if attachment.size > max_size {
+            return Err(format!("Tệp quá lớn, tối đa {} KB", max_size / 1024));
+        }
+        match self.content {
+            Some(Ok(raw)) => Ok((attachment.filename.clone(), raw)),
+            Some(Err(err)) => {
+                warn!(?err, "unable to download attachment");
+                Err("Không thể tải tệp, vui lòng thử lại sau".to_owned())
+            }
+            None => Err("Không thể tải tệp, vui lòng thử lại sau".to_owned()),
+        }
+    }
+}
+
+impl From<(Autocomplete<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Autocomplete<'_>, &AppState)) -> Self {
+        let input = value.input.to_lowercase();
+        let data = state.storage.read();
+        let guild_names = match value.option {
+            "deck" => value
+                .guild
+                .and_then(|guild| data.card_decks.get(&guild))
+                .map(|decks| decks.keys().map(String::as_str).collect::<Vec<_>>()),
+            _ => value
+                .guild
+                .and_then(|guild| data.answer_books.get(&guild))
+                .map(|books| books.keys().map(String::as_str).collect()),
+        };
+        let builtins = match value.option {
+            "deck" => BUILTIN_DECKS,
+            _ => &[],
+        };
+        let candidates = builtins
+            .iter()
+            .map(|builtin| (builtin.label, builtin.id))
+            .chain(
+                guild_names
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|name| (name, name)),
+            );
+        let choices = candidates
+            .filter(|(label, id)| {
+                label.to_lowercase().contains(&input) || id.to_lowercase().contains(&input)
+            })
+            // Discord accepts up to 25 choices
+            .take(25)
+            .map(|(label, id)| CommandOptionChoice {
+                name: label.to_owned(),
+                name_localizations: None,
+                value: CommandOptionChoiceValue::String(id.to_owned()),
+            })
+            .collect();
+
+        Self {
+            choices: Some(choices),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<(Admin<'_>, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Admin<'_>, &AppState)) -> Self {
+        if !state.is_owner(value.author) {
+            return (
+                Error {
+                    error: "Chỉ chủ bot mới dùng được lệnh này".to_owned(),
+                },
+                state,
+            )
+                .into();
+        }
+
+        let content = match value.action {
+            AdminAction::Stats => {
+                let uptime = state.started_at.elapsed().as_secs();
+                let guilds = state.guilds.read().unwrap().len();
+                let counts = state.metrics.command_counts();
+                let total = counts.values().sum::<u64>();
+                let mut counts = counts.into_iter().collect::<Vec<_>>();
+                counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+                let commands = counts
+                    .iter()
+                    .map(|(name, count)| format!("`{name}`: {count}"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let embed = EmbedBuilder::new()
+                    .title("Thống kê")
+                    .field(
+                        EmbedFieldBuilder::new(
+                            "Thời gian chạy",
+                            format!(
+                                "{}d {}h {}m",
+                                uptime / 86400,
+                                uptime % 86400 / 3600,
+                                uptime % 3600 / 60
+                            ),
+                        )
+                        .inline(),
+                    )
+                    .field(EmbedFieldBuilder::new("Số server", guilds.to_string()).inline())
+                    .field(EmbedFieldBuilder::new("Số lệnh đã dùng", total.to_string()).inline())
+                    .color(state.config.colors.primary);
+                let embed = if commands.is_empty() {
+                    embed
+                } else {
+                    embed.description(commands)
+                };
+                return Self {
+                    embeds: vec![embed.build()],
+                    ephemeral: true,
+                    ..Default::default()
+                };
+            }
+            AdminAction::Reload => match data_registry::reload() {
+                Ok(()) => "Đã tải lại dữ liệu".to_owned(),
+                Err(err) => {
+                    warn!(?err, "unable to reload static data");
+                    return (
+                        Error {
+                            error: format!(
+                                "Dữ liệu mới không hợp lệ, vẫn giữ dữ liệu cũ:\n```{err:#}```"
+                            ),
+                        },
+                        state,
+                    )
+                        .into();
+                }
+            },
+            AdminAction::Announce { message, channel } => {
+                let channels = match channel {
+                    Some(channel) => vec![channel],
+                    None => Vec::new(),
+                };
+                let target = match channel {
+                    Some(channel) => channel.mention().to_string(),
+                    None => "mọi server".to_owned(),
+                };
+                tokio::spawn(announce(state.clone(), message.to_owned(), channels));
+                format!("Đang gửi thông báo tới {target}")
+            }
+            AdminAction::BlocklistAdd(None) | AdminAction::BlocklistRemove(None) => {
+                return (
+                    Error {
+                        error: "Id không hợp lệ".to_owned(),
+                    },
+                    state,
+                )
+                    .into()
+            }
+            AdminAction::BlocklistAdd(Some(target)) => {
+                match state.storage.update(|data| data.blocklist.insert(target)) {
+                    Ok(true) => {
+                        if let BlockTarget::Guild(guild) = target {
+                            if state.guilds.read().unwrap().contains(&guild) {
+                                let state = state.clone();
+                                tokio::spawn(
+                                    async move { state.bot.leave_blocked_guild(guild).await },
+                                );
+                            }
+                        }
+                        format!("Đã chặn {target}")
+                    }
+                    Ok(false) => format!("{target} đã bị chặn từ trước"),
+                    Err(err) => {
+                        warn!(?err, "unable to update blocklist");
+                        "Không thể lưu danh sách chặn, vui lòng thử lại sau".to_owned()
+                    }
+                }
+            }
+            AdminAction::BlocklistRemove(Some(target)) => {
+                match state.storage.update(|data| data.blocklist.remove(target)) {
+                    Ok(true) => format!("Đã bỏ chặn {target}"),
+                    Ok(false) => format!("{target} không bị chặn"),
+                    Err(err) => {
+                        warn!(?err, "unable to update blocklist");
+                        "Không thể lưu danh sách chặn, vui lòng thử lại sau".to_owned()
+                    }
+                }
+            }
+            AdminAction::BlocklistList => {
+                let storage = state.storage.read();
+                let blocklist = &storage.blocklist;
+                let users = blocklist
+                    .users
+                    .iter()
+                    .map(|id| BlockTarget::User(*id).to_string());
+                let guilds = blocklist
+                    .guilds
+                    .iter()
+                    .map(|id| BlockTarget::Guild(*id).to_string());
+                let list = users.chain(guilds).collect::<Vec<_>>();
+                if list.is_empty() {
+                    "Danh sách chặn trống".to_owned()
+                } else {
+                    list.join("\n")
+                }
+            }
+            AdminAction::Shutdown => {
+                warn!(user = %value.author, "shutdown requested");
+                state.shutdown.notify_one();
+                "Đang tắt bot".to_owned()
+            }
+        };
+        Self {
+            content: content.into(),
+            ephemeral: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Post `message` to `channels`, or to the system channel of every guild when empty
+async fn announce(state: AppState, message: String, mut channels: Vec<Id<ChannelMarker>>) {
+    if channels.is_empty() {
+        let guilds = state.guilds.read().unwrap().clone();
+        for guild in guilds {
+            let guild = async { anyhow::Ok(state.bot.guild(guild).await?.model().await?) };
+            match guild.await {
+                Ok(guild) => channels.extend(guild.system_channel_id),
+                Err(err) => warn!(?err, "unable to fetch guild for announcement"),
+            }
+        }
+    }
+    let (mut sent, mut failed) = (0, 0);
+    for channel in channels {
+        match state.bot.create_message(channel).content(&message).await {
+            Ok(_) => sent += 1,
+            Err(err) => {
+                warn!(?err, %channel, "unable to send announcement");
+                failed += 1;
+            }
+        }
+    }
+    info!(sent, failed, "announcement sent");
+}
+
+impl From<(About, &AppState)> for ResponseData<'_> {
+    fn from((_value, state): (About, &AppState)) -> Self {
+        use crate::models::data_registry::About;
 
         let embeds = vec![EmbedBuilder::new()
             .title("About")
-            .description(&**ABOUT)
-            .color(constants::color::PRIMARY)
+            .description(&**About::text())
+            .color(state.config.colors.primary)
             .build()];
         Self {
             embeds,
@@ -232,11 +1182,11 @@ impl From<About> for ResponseData<'_> {
         }
     }
 }
-impl From<Error> for ResponseData<'_> {
-    fn from(value: Error) -> Self {
+impl From<(Error, &AppState)> for ResponseData<'_> {
+    fn from((value, state): (Error, &AppState)) -> Self {
         let embeds = vec![EmbedBuilder::new()
             .description(value.error)
-            .color(color::ERROR)
+            .color(state.config.colors.error)
             .build()];
         Self {
             embeds,
@@ -248,6 +1198,15 @@ impl From<Error> for ResponseData<'_> {
 
 impl From<ResponseData<'_>> for InteractionResponse {
     fn from(value: ResponseData) -> Self {
+        if let Some(choices) = value.choices {
+            return InteractionResponse {
+                kind: InteractionResponseType::ApplicationCommandAutocompleteResult,
+                data: Some(InteractionResponseData {
+                    choices: Some(choices),
+                    ..Default::default()
+                }),
+            };
+        }
         InteractionResponse {
             kind: InteractionResponseType::ChannelMessageWithSource,
             data: Some(InteractionResponseData {
@@ -1,21 +1,185 @@
 mod commands;
 mod constants;
+mod emoji_sync;
 mod engine;
 mod extensions;
 mod handler;
 mod models;
+mod server;
+mod validate;
 
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use commands::CommandRegister;
 use engine::Engine;
+use models::{
+    app_state::AppState,
+    book_of_answers::BookOfAnswers,
+    config::{Config, Log, LogFormat},
+    data_registry,
+};
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use tracing_subscriber::EnvFilter;
+use twilight_model::id::{marker::GuildMarker, Id};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// TOML config file, defaults to `boothebot.toml` when present
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Override a setting, e.g. `--set limits.max_dice=50`
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    overrides: Vec<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check the datasets for issues, exits with a non-zero code if any is found
+    ValidateData {
+        /// Directory containing the datasets, defaults to `static`
+        dir: Option<PathBuf>,
+    },
+    /// Upload a directory of images as application emojis and record them in the asset manifest
+    SyncEmojis {
+        /// Directory of images, `name.png` or `name.<variant>.gif` for several variants
+        dir: PathBuf,
+        /// Manifest to update, defaults to the one in `BOOTHEBOT_DATA_DIR` or `static`
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+        /// Send the requests over plain http to this host, overrides `discord.proxy`
+        #[arg(long)]
+        proxy: Option<String>,
+    },
+    /// Convert a legacy `BookOfAnswers.txt` into the JSON format
+    ConvertBook {
+        input: PathBuf,
+        /// Defaults to the input path with a `.json` extension
+        output: Option<PathBuf>,
+    },
+    /// Register the slash commands, only the changed ones are sent
+    Register {
+        /// Register to this guild instead of globally, overrides `discord.dev_guild`
+        #[arg(long)]
+        guild: Option<Id<GuildMarker>>,
+    },
+    /// Remove every slash command, from `--guild` or globally
+    Unregister {
+        #[arg(long)]
+        guild: Option<Id<GuildMarker>>,
+    },
+}
 
 #[tokio::main]
-async fn main() {
-    // Setup tracing
-    tracing_subscriber::fmt().init();
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mut config = match Config::load(cli.config.as_deref(), &cli.overrides) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{err:#}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(dir) = &config.paths.data_dir {
+        data_registry::set_data_dir(dir.clone());
+    }
+
+    init_tracing(&config.log);
+
+    match cli.command {
+        Some(Command::ValidateData { dir }) => return validate::run(dir),
+        Some(Command::SyncEmojis {
+            dir,
+            manifest,
+            proxy,
+        }) => {
+            return match emoji_sync::run(dir, manifest, proxy.or(config.discord.proxy)).await {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("{err:#}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some(Command::ConvertBook { input, output }) => {
+            return match convert_book(&input, output) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("{err:#}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some(command @ (Command::Register { guild } | Command::Unregister { guild })) => {
+            if guild.is_some() {
+                config.discord.dev_guild = guild;
+            }
+            let unregister = matches!(command, Command::Unregister { .. });
+            return match register(config, unregister).await {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("{err:#}");
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        None => {}
+    }
+
+    // Fail fast on malformed static data
+    if let Err(err) = data_registry::init() {
+        eprintln!("{err:#}");
+        return ExitCode::FAILURE;
+    }
 
     let token = std::env::var("DISCORD_TOKEN").expect("env var `DISCORD_TOKEN` should exists");
 
-    let engine = Engine::new(token)
+    let engine = Engine::new(token, config)
         .await
         .expect("Engine should be sucessfully initialized");
     engine.run().await;
+    ExitCode::SUCCESS
+}
+
+fn init_tracing(log: &Log) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&log.filter));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match log.format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Pretty => builder.pretty().init(),
+        // Span fields such as the guild and interaction id sit next to each event
+        LogFormat::Json => builder.json().flatten_event(true).init(),
+    }
+}
+
+fn convert_book(input: &Path, output: Option<PathBuf>) -> anyhow::Result<()> {
+    let raw = std::fs::read_to_string(input)
+        .with_context(|| format!("unable to read `{}`", input.display()))?;
+    let book = BookOfAnswers::from_legacy(&raw)?;
+    let output = output.unwrap_or_else(|| input.with_extension("json"));
+    std::fs::write(&output, book.to_json()?)
+        .with_context(|| format!("unable to write `{}`", output.display()))?;
+    println!(
+        "converted {} answers into `{}`",
+        book.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Update the slash commands without connecting to the gateway
+async fn register(config: Config, unregister: bool) -> anyhow::Result<()> {
+    let token = std::env::var("DISCORD_TOKEN").context("env var `DISCORD_TOKEN` should exists")?;
+    let state = AppState::new_with_token(token, config).await?;
+    let register = CommandRegister::new(state);
+    if unregister {
+        register.unregister().await
+    } else {
+        register.register().await
+    }
 }
@@ -1,7 +1,24 @@
 use super::*;
 use anyhow::Result;
+use assets::Assets;
 use bot::{Bot, BotInfo};
-use std::{ops::Deref, sync::Arc};
+use config::Config;
+use cooldown::Cooldowns;
+use error_report::ErrorReporter;
+use health::Health;
+use metrics::Metrics;
+use rustc_hash::FxHashSet;
+use std::{
+    ops::Deref,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+use storage::Storage;
+use tokio::sync::Notify;
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
 
 #[derive(Debug, Clone)]
 pub struct AppState(Arc<AppStateInner>);
@@ -10,18 +27,56 @@ pub struct AppState(Arc<AppStateInner>);
 pub struct AppStateInner {
     pub bot: Bot,
     pub info: BotInfo,
+    pub config: Config,
+    pub storage: Storage,
+    pub assets: Assets,
+    /// Client for non-Discord downloads such as attachments
+    pub http: reqwest::Client,
+    pub metrics: Metrics,
+    pub health: Health,
+    pub reporter: ErrorReporter,
+    pub cooldowns: Cooldowns,
+    pub started_at: Instant,
+    /// Guilds the bot is in, from the gateway
+    pub guilds: RwLock<FxHashSet<Id<GuildMarker>>>,
+    /// Stops the engine like a signal does
+    pub shutdown: Notify,
 }
 
 impl AppState {
-    pub async fn new_with_token(token: impl Into<String>) -> Result<Self> {
-        let bot = Bot::new_with_token(token.into());
+    pub async fn new_with_token(token: impl Into<String>, config: Config) -> Result<Self> {
+        let bot = Bot::new_with_proxy(token, config.discord.proxy.clone());
         let info = BotInfo::init(&bot).await?;
-        let inner = AppStateInner { bot, info };
+        let storage = Storage::open(config.paths.storage.clone())?;
+        let assets = Assets::load()?;
+        let http = reqwest::Client::new();
+        let inner = AppStateInner {
+            bot,
+            info,
+            config,
+            storage,
+            assets,
+            http,
+            metrics: Metrics::new(),
+            health: Health::new(),
+            reporter: ErrorReporter::default(),
+            cooldowns: Cooldowns::default(),
+            started_at: Instant::now(),
+            guilds: Default::default(),
+            shutdown: Notify::new(),
+        };
         let state = Self(inner.into());
         Ok(state)
     }
 }
 
+impl AppStateInner {
+    /// The application owner or one of `admin.owners`
+    pub fn is_owner(&self, user: Id<UserMarker>) -> bool {
+        user == self.info.owner || self.config.admin.owners.contains(&user)
+    }
+}
+
 impl Deref for AppState {
     type Target = AppStateInner;
 
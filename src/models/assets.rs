@@ -0,0 +1,143 @@
+use crate::models::data_registry::ASSETS;
+use anyhow::{ensure, Context, Result};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Display};
+use twilight_http::request::channel::reaction::RequestReactionType;
+use twilight_model::{
+    channel::message::EmojiReactionType,
+    id::{marker::EmojiMarker, Id},
+};
+
+/// Logical names mapped to the emojis and urls of one Discord application.
+///
+/// Anything missing from the manifest falls back to Unicode or is left out,
+/// so a self-hosted bot works with an empty manifest.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Assets {
+    /// Each name may have several variants, one is picked at random
+    #[serde(default)]
+    pub emojis: BTreeMap<String, Variants>,
+    /// Url templates, see `Assets::url`
+    #[serde(default)]
+    pub urls: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Variants {
+    One(CustomEmoji),
+    Many(Vec<CustomEmoji>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomEmoji {
+    pub id: Id<EmojiMarker>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub animated: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Emoji {
+    Custom(CustomEmoji),
+    Unicode(&'static str),
+}
+
+impl Variants {
+    pub fn as_slice(&self) -> &[CustomEmoji] {
+        match self {
+            Variants::One(emoji) => std::slice::from_ref(emoji),
+            Variants::Many(emojis) => emojis,
+        }
+    }
+}
+
+impl CustomEmoji {
+    pub fn url(&self) -> String {
+        let ext = if self.animated { "gif" } else { "webp" };
+        format!("https://cdn.discordapp.com/emojis/{}.{ext}", self.id)
+    }
+}
+
+impl Emoji {
+    pub fn reaction(self) -> EmojiReactionType {
+        match self {
+            Emoji::Custom(CustomEmoji { id, animated }) => EmojiReactionType::Custom {
+                animated,
+                id,
+                name: None,
+            },
+            Emoji::Unicode(name) => EmojiReactionType::Unicode {
+                name: name.to_owned(),
+            },
+        }
+    }
+    /// For reacting to a message
+    pub fn request(&self) -> RequestReactionType<'_> {
+        match self {
+            Emoji::Custom(CustomEmoji { id, .. }) => RequestReactionType::Custom {
+                id: *id,
+                name: None,
+            },
+            Emoji::Unicode(name) => RequestReactionType::Unicode { name },
+        }
+    }
+}
+
+impl Display for Emoji {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Emoji::Custom(CustomEmoji { id, animated: true }) => write!(f, "<a:a:{id}>"),
+            Emoji::Custom(CustomEmoji { id, .. }) => write!(f, "<:e:{id}>"),
+            Emoji::Unicode(name) => f.write_str(name),
+        }
+    }
+}
+
+impl Assets {
+    pub fn load() -> Result<Self> {
+        let raw = ASSETS.read()?;
+        Self::from_json(&raw).with_context(|| format!("`{}` should be valid", ASSETS.name))
+    }
+
+    pub fn from_json(raw: &str) -> Result<Self> {
+        let assets: Self = serde_json::from_str(raw)?;
+        for (name, variants) in &assets.emojis {
+            ensure!(
+                !variants.as_slice().is_empty(),
+                "emoji `{name}` should have at least one variant"
+            );
+        }
+        for (name, url) in &assets.urls {
+            ensure!(
+                url.starts_with("https://"),
+                "url `{name}` should be an https url"
+            );
+        }
+        Ok(assets)
+    }
+
+    pub fn custom(&self, name: &str, rng: &mut impl Rng) -> Option<CustomEmoji> {
+        self.emojis.get(name)?.as_slice().choose(rng).copied()
+    }
+
+    /// The custom emoji named `name`, or `fallback` when it is not in the manifest
+    pub fn emoji(&self, name: &str, fallback: &'static str) -> Emoji {
+        self.emoji_with(name, fallback, &mut rand::thread_rng())
+    }
+
+    pub fn emoji_with(&self, name: &str, fallback: &'static str, rng: &mut impl Rng) -> Emoji {
+        self.custom(name, rng)
+            .map_or(Emoji::Unicode(fallback), Emoji::Custom)
+    }
+
+    /// Fill the `{key}` placeholders of the url template `name`
+    pub fn url(&self, name: &str, args: &[(&str, &str)]) -> Option<String> {
+        let url = args
+            .iter()
+            .fold(self.urls.get(name)?.clone(), |url, (key, value)| {
+                url.replace(&format!("{{{key}}}"), value)
+            });
+        Some(url)
+    }
+}
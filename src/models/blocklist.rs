@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt::Display};
+use twilight_mention::Mention;
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
+
+/// Users and guilds the bot ignores, managed with `/admin blocklist`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Blocklist {
+    pub users: BTreeSet<Id<UserMarker>>,
+    pub guilds: BTreeSet<Id<GuildMarker>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BlockTarget {
+    User(Id<UserMarker>),
+    Guild(Id<GuildMarker>),
+}
+
+impl Blocklist {
+    pub fn is_blocked(&self, user: Option<Id<UserMarker>>, guild: Option<Id<GuildMarker>>) -> bool {
+        user.is_some_and(|user| self.users.contains(&user))
+            || guild.is_some_and(|guild| self.guilds.contains(&guild))
+    }
+    /// `false` when already blocked
+    pub fn insert(&mut self, target: BlockTarget) -> bool {
+        match target {
+            BlockTarget::User(id) => self.users.insert(id),
+            BlockTarget::Guild(id) => self.guilds.insert(id),
+        }
+    }
+    /// `false` when not blocked
+    pub fn remove(&mut self, target: BlockTarget) -> bool {
+        match target {
+            BlockTarget::User(id) => self.users.remove(&id),
+            BlockTarget::Guild(id) => self.guilds.remove(&id),
+        }
+    }
+}
+
+impl Display for BlockTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockTarget::User(id) => write!(f, "user {} (`{id}`)", id.mention()),
+            BlockTarget::Guild(id) => write!(f, "server `{id}`"),
+        }
+    }
+}
@@ -1,31 +1,230 @@
-use crate::models::seed_generator::{SeedGenerator, TimeHash};
-use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
-use std::{ops::Deref, sync::LazyLock};
+use crate::models::{
+    data_registry::{self, Reloadable, BOOK_OF_ANSWERS},
+    seed_generator::{SeedGenerator, TimeHash},
+};
+use anyhow::{ensure, Result};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{ops::Deref, sync::Arc};
 use twilight_model::id::{marker::UserMarker, Id};
 
-type Inner = Box<[Box<str>]>;
+type Inner = Box<[Answer]>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
 pub struct BookOfAnswers(Inner);
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Answer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vi: Option<Box<str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    en: Option<Box<str>>,
+    #[serde(default = "Answer::default_category")]
+    category: Box<str>,
+    #[serde(default)]
+    tone: Tone,
+    #[serde(default = "Answer::default_weight")]
+    weight: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tone {
+    Positive,
+    #[default]
+    Neutral,
+    Negative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Vi,
+    En,
+}
+
+/// Relative weights of each tone when answering yes/no questions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ToneDistribution {
+    pub positive: u8,
+    pub neutral: u8,
+    pub negative: u8,
+}
+
+impl Default for ToneDistribution {
+    fn default() -> Self {
+        // Same ratio as a classic magic 8-ball
+        Self {
+            positive: 50,
+            neutral: 25,
+            negative: 25,
+        }
+    }
+}
+
+impl ToneDistribution {
+    pub fn is_empty(&self) -> bool {
+        self.positive == 0 && self.neutral == 0 && self.negative == 0
+    }
+    fn choose(&self, rng: &mut impl Rng) -> Tone {
+        let total = u32::from(self.positive) + u32::from(self.neutral) + u32::from(self.negative);
+        if total == 0 {
+            return Tone::Neutral;
+        }
+        let roll = rng.gen_range(0..total);
+        if roll < u32::from(self.positive) {
+            Tone::Positive
+        } else if roll < u32::from(self.positive) + u32::from(self.neutral) {
+            Tone::Neutral
+        } else {
+            Tone::Negative
+        }
+    }
+}
+
+impl Tone {
+    /// Legacy entries may start with a `[+]`, `[=]` or `[-]` tag, untagged ones are neutral
+    fn split_tag(entry: &str) -> (Self, &str) {
+        let tone = match entry.get(..3) {
+            Some("[+]") => Tone::Positive,
+            Some("[-]") => Tone::Negative,
+            Some("[=]") => Tone::Neutral,
+            _ => return (Tone::Neutral, entry),
+        };
+        (tone, &entry[3..])
+    }
+}
+
+impl Language {
+    /// Map a Discord locale (`vi`, `en-US`, ...) to a supported language
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        match locale.split('-').next()? {
+            "vi" => Some(Language::Vi),
+            "en" => Some(Language::En),
+            _ => None,
+        }
+    }
+}
+
+impl Answer {
+    fn default_category() -> Box<str> {
+        "general".into()
+    }
+    fn default_weight() -> u32 {
+        1
+    }
+    /// Render in one language, or both when `language` is `None`.
+    /// Falls back to whichever text exists.
+    pub fn render(&self, language: Option<Language>) -> String {
+        match (language, self.vi.as_deref(), self.en.as_deref()) {
+            (Some(Language::Vi), Some(vi), _) | (_, Some(vi), None) => format!("***{vi}***"),
+            (Some(Language::En), _, Some(en)) | (_, None, Some(en)) => format!("*{en}*"),
+            (_, Some(vi), Some(en)) => format!("***{vi}**\n/{en}/*"),
+            (_, None, None) => String::new(),
+        }
+    }
+}
+
 impl BookOfAnswers {
-    fn get_instance() -> &'static Self {
-        static INSTANCE: LazyLock<BookOfAnswers> = LazyLock::new(|| {
-            BookOfAnswers(
-                std::fs::read_to_string("static/BookOfAnswers.txt")
-                    .expect("`BookOfAnswers.txt` file should exist")
-                    .split_inclusive("/*")
-                    .map(str::trim_start)
-                    .map(Into::into)
-                    .collect::<Vec<Box<str>>>()
-                    .into_boxed_slice(),
-            )
-        });
+    pub fn reloadable() -> &'static Reloadable<Self> {
+        static INSTANCE: Reloadable<BookOfAnswers> =
+            Reloadable::new("BookOfAnswers", BookOfAnswers::load);
         &INSTANCE
     }
+    fn get_instance() -> Arc<Self> {
+        Self::reloadable().get()
+    }
+    fn load() -> Result<Self> {
+        // An overriding book may still use the legacy text format
+        match data_registry::override_path(data_registry::LEGACY_BOOK_OF_ANSWERS) {
+            Some(legacy) if data_registry::override_path(BOOK_OF_ANSWERS.name).is_none() => {
+                Self::from_legacy(&std::fs::read_to_string(legacy)?)
+            }
+            _ => Self::from_json(&BOOK_OF_ANSWERS.read()?),
+        }
+    }
 
-    pub fn draw(content: Option<&str>, author: Id<UserMarker>) -> &'static str {
-        let book = Self::get_instance();
+    pub fn from_json(raw: &str) -> Result<Self> {
+        Self::new(serde_json::from_str(raw)?)
+    }
+
+    /// Plain text book, either in the legacy format or one answer per line
+    pub fn from_text(raw: &str) -> Result<Self> {
+        if raw.contains("/*") {
+            return Self::from_legacy(raw);
+        }
+        let answers = raw
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(Tone::split_tag)
+            .map(|(tone, text)| Answer {
+                vi: Some(text.trim().into()),
+                en: None,
+                category: Answer::default_category(),
+                tone,
+                weight: Answer::default_weight(),
+            })
+            .collect();
+        Self::new(answers)
+    }
+
+    /// Convert the legacy `/*` separated text format
+    pub fn from_legacy(raw: &str) -> Result<Self> {
+        let answers = raw
+            .split_inclusive("/*")
+            .map(str::trim_start)
+            .filter(|entry| !entry.is_empty())
+            .map(Tone::split_tag)
+            .map(|(tone, text)| {
+                // `***Tiếng Việt**\n/English/*`
+                let bilingual = text
+                    .strip_prefix("***")
+                    .and_then(|text| text.strip_suffix("/*"))
+                    .and_then(|text| text.split_once("**\n/"));
+                let (vi, en) = match bilingual {
+                    Some((vi, en)) => (vi.trim(), Some(en.trim().into())),
+                    None => (text.strip_suffix("/*").unwrap_or(text).trim(), None),
+                };
+                Answer {
+                    vi: Some(vi.into()),
+                    en,
+                    category: Answer::default_category(),
+                    tone,
+                    weight: Answer::default_weight(),
+                }
+            })
+            .collect();
+        Self::new(answers)
+    }
+
+    /// Same layout as `BookOfAnswers.json`, one answer per line
+    pub fn to_json(&self) -> Result<String> {
+        let answers = self
+            .0
+            .iter()
+            .map(|answer| Ok(format!("  {}", serde_json::to_string(answer)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(format!("[\n{}\n]\n", answers.join(",\n")))
+    }
+
+    fn new(answers: Vec<Answer>) -> Result<Self> {
+        ensure!(!answers.is_empty(), "BookOfAnswers should not be empty");
+        for (idx, answer) in answers.iter().enumerate() {
+            let blank = |text: Option<&str>| text.is_none_or(|text| text.trim().is_empty());
+            ensure!(
+                !blank(answer.vi.as_deref()) || !blank(answer.en.as_deref()),
+                "answer #{idx} should have either `vi` or `en` text"
+            );
+            ensure!(
+                answer.weight > 0,
+                "answer #{idx} should have a positive weight"
+            );
+        }
+        Ok(Self(answers.into()))
+    }
+
+    fn rng(content: Option<&str>, author: Id<UserMarker>) -> StdRng {
         match content {
             Some(content) => {
                 let seed = SeedGenerator::default()
@@ -33,15 +232,79 @@ impl BookOfAnswers {
                     .hash(author)
                     .hash(content)
                     .finish();
-                let mut rng = StdRng::seed_from_u64(seed);
-                book.0.choose(&mut rng)
-            }
-            None => {
-                let mut rng = rand::thread_rng();
-                book.0.choose(&mut rng)
+                StdRng::seed_from_u64(seed)
             }
+            None => StdRng::from_rng(rand::thread_rng()).expect("thread_rng should not fail"),
         }
-        .expect("BookOfAnswers should not be empty")
+    }
+
+    fn choose<'a>(pool: &[&'a Answer], rng: &mut impl Rng) -> Option<&'a Answer> {
+        pool.choose_weighted(rng, |answer| answer.weight)
+            .ok()
+            .copied()
+    }
+
+    /// Draw and render an answer from the global book
+    pub fn draw(
+        content: Option<&str>,
+        author: Id<UserMarker>,
+        category: Option<&str>,
+        language: Option<Language>,
+    ) -> String {
+        Self::get_instance()
+            .draw_from(content, author, category)
+            .render(language)
+    }
+
+    /// Draw an answer, restricted to `category` when that category has any entry
+    pub fn draw_from(
+        &self,
+        content: Option<&str>,
+        author: Id<UserMarker>,
+        category: Option<&str>,
+    ) -> &Answer {
+        let mut rng = Self::rng(content, author);
+        let all = self.0.iter().collect::<Vec<_>>();
+        let pool = category
+            .map(|category| {
+                all.iter()
+                    .copied()
+                    .filter(|answer| &*answer.category == category)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|pool| !pool.is_empty())
+            .unwrap_or_else(|| all.clone());
+        Self::choose(&pool, &mut rng).expect("BookOfAnswers should not be empty")
+    }
+
+    /// Yes/no oracle, the tone is picked first then an answer of that tone
+    pub fn ask(
+        content: Option<&str>,
+        author: Id<UserMarker>,
+        distribution: ToneDistribution,
+        language: Option<Language>,
+    ) -> String {
+        let book = Self::get_instance();
+        let mut rng = Self::rng(content, author);
+        let tone = distribution.choose(&mut rng);
+        let all = book.0.iter().collect::<Vec<_>>();
+        let pool = all
+            .iter()
+            .copied()
+            .filter(|answer| answer.tone == tone)
+            .collect::<Vec<_>>();
+        Self::choose(&pool, &mut rng)
+            .or_else(|| Self::choose(&all, &mut rng))
+            .expect("BookOfAnswers should not be empty")
+            .render(language)
+    }
+}
+
+impl<'de> Deserialize<'de> for BookOfAnswers {
+    /// Stored books go through the same validation as uploaded ones
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let answers = Vec::deserialize(deserializer)?;
+        Self::new(answers).map_err(serde::de::Error::custom)
     }
 }
 
@@ -52,3 +315,68 @@ impl Deref for BookOfAnswers {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_tone_has_answers() {
+        let book = BookOfAnswers::get_instance();
+        for tone in [Tone::Positive, Tone::Neutral, Tone::Negative] {
+            assert!(
+                book.iter().any(|answer| answer.tone == tone),
+                "no {tone:?} answer"
+            );
+        }
+    }
+
+    #[test]
+    fn legacy_format() {
+        let book = BookOfAnswers::from_legacy(
+            "***Mở lòng đón nhận**\n/Open your heart/*\n[-]***Chuyện không như bạn nghĩ**\n/Not what you think/*\n[+]Chắc chắn/*\n",
+        )
+        .unwrap();
+        assert_eq!(book.len(), 3);
+        assert_eq!(book[0].vi.as_deref(), Some("Mở lòng đón nhận"));
+        assert_eq!(book[0].en.as_deref(), Some("Open your heart"));
+        assert_eq!(book[0].tone, Tone::Neutral);
+        assert_eq!(book[1].tone, Tone::Negative);
+        assert_eq!(book[2].vi.as_deref(), Some("Chắc chắn"));
+        assert_eq!(book[2].en, None);
+        assert_eq!(book[2].tone, Tone::Positive);
+        assert_eq!(
+            book[0].render(None),
+            "***Mở lòng đón nhận**\n/Open your heart/*"
+        );
+    }
+
+    #[test]
+    fn text_format() {
+        let book = BookOfAnswers::from_text("Có\n\n[-] Không\n  [=]Có thể  \n").unwrap();
+        let answers = book
+            .iter()
+            .map(|answer| (answer.vi.as_deref().unwrap(), answer.tone))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            answers,
+            [
+                ("Có", Tone::Neutral),
+                ("Không", Tone::Negative),
+                ("Có thể", Tone::Neutral)
+            ]
+        );
+        assert!(BookOfAnswers::from_text("\n \n").is_err());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let book = BookOfAnswers::from_legacy("[+]***Có**\n/Yes/*").unwrap();
+        let json = book.to_json().unwrap();
+        let book = BookOfAnswers::from_json(&json).unwrap();
+        assert_eq!(book[0].render(Some(Language::En)), "*Yes*");
+        assert_eq!(book[0].tone, Tone::Positive);
+        assert!(BookOfAnswers::from_json(r#"[{"vi": "Có", "weight": 0}]"#).is_err());
+        assert!(BookOfAnswers::from_json(r#"[{"category": "advice"}]"#).is_err());
+    }
+}
@@ -2,9 +2,10 @@ use std::ops::Deref;
 
 use anyhow::Result;
 use serde::Deserialize;
+use tracing::warn;
 use twilight_mention::Mention;
 use twilight_model::id::{
-    marker::{ApplicationMarker, GenericMarker, UserMarker},
+    marker::{ApplicationMarker, GenericMarker, GuildMarker, UserMarker},
     Id,
 };
 
@@ -17,15 +18,27 @@ pub struct Bot(BotClient);
 pub struct BotInfo {
     pub appid: Id<ApplicationMarker>,
     pub mention: Box<str>,
+    /// Application owner, or the team owner for team applications
+    pub owner: Id<UserMarker>,
 }
 
 impl Bot {
-    pub fn new_with_token(token: impl Into<String>) -> Self {
-        let client = BotClient::builder()
+    /// Send requests over plain http to `proxy` instead of Discord, e.g. a mock server
+    pub fn new_with_proxy(token: impl Into<String>, proxy: Option<String>) -> Self {
+        let mut builder = BotClient::builder()
             .token(token.into())
-            .default_allowed_mentions(Default::default())
-            .build();
-        Self(client)
+            .default_allowed_mentions(Default::default());
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy, true);
+        }
+        Self(builder.build())
+    }
+    /// Leave a guild on the blocklist, failures are only logged
+    pub async fn leave_blocked_guild(&self, guild: Id<GuildMarker>) {
+        let _ = self
+            .leave_guild(guild)
+            .await
+            .inspect_err(|err| warn!(?err, %guild, "unable to leave blocked guild"));
     }
 }
 
@@ -43,9 +56,34 @@ impl BotInfo {
         struct IdOnly {
             id: Id<GenericMarker>,
         }
+        #[derive(Deserialize)]
+        struct TeamOwner {
+            owner_user_id: Id<UserMarker>,
+        }
+        #[derive(Deserialize)]
+        struct Owners {
+            owner: Option<IdOnly>,
+            team: Option<TeamOwner>,
+        }
         let bytes = bot.current_user().await?.bytes().await?;
         let appid = serde_json::from_slice::<IdOnly>(&bytes)?.id.cast();
         let mention = appid.cast::<UserMarker>().mention().to_string().into();
-        Ok(Self { appid, mention })
+
+        let bytes = bot.current_user_application().await?.bytes().await?;
+        let owners = serde_json::from_slice::<Owners>(&bytes)?;
+        let owner = match owners {
+            Owners {
+                team: Some(team), ..
+            } => team.owner_user_id,
+            Owners {
+                owner: Some(owner), ..
+            } => owner.id.cast(),
+            _ => anyhow::bail!("application should have an owner"),
+        };
+        Ok(Self {
+            appid,
+            mention,
+            owner,
+        })
     }
 }
@@ -1,17 +1,22 @@
 use crate::{
-    constants::color,
+    constants::limit,
     models::{
+        assets::Assets,
+        config::Config,
         custom_id::CustomId,
+        data_registry::{self, Reloadable, StaticFile},
         seed_generator::{SeedGenerator, TimeHash},
     },
 };
+use anyhow::{ensure, Context, Result};
 use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
-use serde::Deserialize;
-use std::{borrow::Cow, ops::Deref, sync::LazyLock};
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{borrow::Cow, ops::Deref, sync::Arc};
 use twilight_model::{
     channel::message::{
         component::{ActionRow, Button, ButtonStyle},
-        Component, Embed, EmojiReactionType,
+        Component, Embed,
     },
     id::{marker::UserMarker, Id},
 };
@@ -19,18 +24,32 @@ use twilight_util::builder::embed::{EmbedBuilder, ImageSource};
 
 type Inner = Box<[ClowCard]>;
 
-pub struct ClowCardDeck(Inner);
+#[derive(Debug, Clone)]
+pub struct ClowCardDeck {
+    cards: Inner,
+    /// Prepended to card names in titles, e.g. "The Windy"
+    title_prefix: &'static str,
+}
 
+#[derive(Debug, Clone, Serialize)]
 pub struct ClowCard {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
     name: Box<str>,
     meaning: Box<str>,
-    img_id: u64,
+    message: Box<str>,
+    warning: Box<str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<Box<str>>,
+    #[serde(skip)]
     full: Box<str>,
 }
 
 #[derive(Deserialize, Clone)]
 struct ClowcardCow<'a> {
-    id: u64,
+    /// Attachment id of the original Clow card artwork
+    #[serde(default)]
+    id: Option<u64>,
     #[serde(borrow)]
     name: Cow<'a, str>,
     #[serde(borrow)]
@@ -39,52 +58,153 @@ struct ClowcardCow<'a> {
     message: Cow<'a, str>,
     #[serde(borrow)]
     warning: Cow<'a, str>,
+    /// Direct artwork url, takes precedence over `id`
+    #[serde(default, borrow)]
+    image: Option<Cow<'a, str>>,
+}
+
+pub struct BuiltinDeck {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub file: StaticFile,
+    pub title_prefix: &'static str,
 }
 
+pub const DEFAULT_DECK: &str = "clow";
+
+pub const BUILTIN_DECKS: &[BuiltinDeck] = &[
+    BuiltinDeck {
+        id: DEFAULT_DECK,
+        label: "Clow",
+        file: data_registry::CLOW_CARDS,
+        title_prefix: "The ",
+    },
+    BuiltinDeck {
+        id: "rws-major",
+        label: "Rider–Waite · Ẩn chính",
+        file: data_registry::RIDER_WAITE_MAJOR,
+        title_prefix: "",
+    },
+    BuiltinDeck {
+        id: "rws-minor",
+        label: "Rider–Waite · Ẩn phụ",
+        file: data_registry::RIDER_WAITE_MINOR,
+        title_prefix: "",
+    },
+];
+
 impl ClowCardDeck {
-    fn get_instance() -> &'static Self {
-        static INSTANCE: LazyLock<ClowCardDeck> = LazyLock::new(|| {
-            let raw = std::fs::read_to_string("static/ClowCardData.json").unwrap();
-            let mut deck = serde_json::from_str::<Vec<ClowcardCow>>(&raw)
-                .expect("ClowCardData should be in correct format")
-                .into_iter()
-                .map(
-                    |ClowcardCow {
-                         id,
-                         name,
-                         meaning,
-                         message,
-                         warning,
-                     }| ClowCard {
-                        full: format!(
-                            "# [ The {name} ]\n\
+    /// Built-in decks, in the order of `BUILTIN_DECKS`
+    pub fn reloadable() -> &'static Reloadable<Box<[Arc<Self>]>> {
+        static INSTANCES: Reloadable<Box<[Arc<ClowCardDeck>]>> =
+            Reloadable::new("ClowCardDeck", ClowCardDeck::load_builtins);
+        &INSTANCES
+    }
+    fn load_builtins() -> Result<Box<[Arc<Self>]>> {
+        BUILTIN_DECKS
+            .iter()
+            .map(|builtin| {
+                let raw = builtin.file.read()?;
+                let deck = ClowCardDeck::from_json(&raw, builtin.title_prefix)
+                    .with_context(|| format!("`{}` should be valid", builtin.file.name))?;
+                Ok(Arc::new(deck))
+            })
+            .collect()
+    }
+
+    pub fn builtin(id: &str) -> Option<Arc<Self>> {
+        let pos = BUILTIN_DECKS.iter().position(|builtin| builtin.id == id)?;
+        Some(Self::reloadable().get()[pos].clone())
+    }
+
+    pub fn from_json(raw: &str, title_prefix: &'static str) -> Result<Self> {
+        Self::new(serde_json::from_str(raw)?, title_prefix)
+    }
+
+    fn new(cards: Vec<ClowcardCow>, title_prefix: &'static str) -> Result<Self> {
+        ensure!(!cards.is_empty(), "deck should not be empty");
+        let mut names = FxHashSet::default();
+        let mut deck = cards
+            .into_iter()
+            .enumerate()
+            .map(
+                |(
+                    idx,
+                    ClowcardCow {
+                        id,
+                        name,
+                        meaning,
+                        message,
+                        warning,
+                        image,
+                    },
+                )| {
+                    let name = name.trim();
+                    ensure!(!name.is_empty(), "card #{idx} should have a name");
+                    // The deck id and card name share the 100 bytes of a custom id
+                    ensure!(
+                        name.len() <= limit::MAX_CARD_NAME,
+                        "card `{name}` name should be at most {} bytes",
+                        limit::MAX_CARD_NAME
+                    );
+                    ensure!(names.insert(name.to_owned()), "card `{name}` is duplicated");
+                    ensure!(
+                        [&meaning, &message, &warning]
+                            .iter()
+                            .all(|text| !text.trim().is_empty()),
+                        "card `{name}` should not have empty text"
+                    );
+                    ensure!(
+                        image
+                            .as_deref()
+                            .is_none_or(|image| image.starts_with("https://")),
+                        "card `{name}` image should be an https url"
+                    );
+
+                    let full = format!(
+                        "# [ {title_prefix}{name} ]\n\
                         ```md\n\
                         ## Ý NGHĨA\n{meaning}\n\n\
                         ## THÔNG ĐIỆP\n{message}\n\n\
                         ## CẢNH BÁO\n{warning}\n\
                         ```"
-                        )
-                        .into(),
+                    );
+                    // Sent as a message content
+                    ensure!(
+                        full.chars().count() <= 2000,
+                        "card `{name}` text should fit in 2000 characters"
+                    );
+                    Ok(ClowCard {
+                        id,
                         name: name.into(),
                         meaning: meaning.into(),
-                        img_id: id,
-                    },
-                )
-                .collect::<Vec<_>>();
-            deck.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+                        message: message.into(),
+                        warning: warning.into(),
+                        image: image.map(Into::into),
+                        full: full.into(),
+                    })
+                },
+            )
+            .collect::<Result<Vec<_>>>()?;
+        deck.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
 
-            ClowCardDeck(deck.into())
-        });
-        &INSTANCE
+        Ok(ClowCardDeck {
+            cards: deck.into(),
+            title_prefix,
+        })
     }
+
     pub fn draw(
+        &self,
+        config: &Config,
+        assets: &Assets,
+        deck_id: &str,
         content: Option<&str>,
         author: Id<UserMarker>,
         amount: Option<usize>,
     ) -> (Vec<Embed>, Vec<Component>) {
-        let deck = Self::get_instance();
         let time = match (content, amount) {
-            (None, None) => TimeHash::Day,
+            (None, None) => TimeHash::Day(config.time.utc_offset_minutes),
             (None, Some(_)) => TimeHash::Second,
             (Some(_), _) => TimeHash::Minute,
         };
@@ -94,14 +214,15 @@ impl ClowCardDeck {
             .hash(author)
             .hash(content)
             .hash(amount)
+            .hash(deck_id)
             .finish();
         let mut rng = StdRng::seed_from_u64(state);
 
         let mut embeds = Vec::with_capacity(amount);
         let mut components = Vec::with_capacity(amount);
 
-        deck.choose_multiple(&mut rng, amount)
-            .map(Self::short)
+        self.choose_multiple(&mut rng, amount)
+            .map(|card| self.short(config, assets, deck_id, card))
             .for_each(|(embed, component)| {
                 embeds.push(embed);
                 components.push(component);
@@ -111,52 +232,55 @@ impl ClowCardDeck {
         (embeds, components)
     }
     fn short(
+        &self,
+        config: &Config,
+        assets: &Assets,
+        deck_id: &str,
         ClowCard {
+            id,
             name,
             meaning,
-            img_id,
+            image,
             ..
         }: &ClowCard,
     ) -> (Embed, Component) {
-        const EMOJI_MAGICBOOK: EmojiReactionType = EmojiReactionType::Custom {
-            animated: false,
-            id: Id::new(1312304913455517737),
-            name: None,
+        let custom_id =
+            CustomId::ButtonCardInfo(Cow::Borrowed(deck_id), Cow::Borrowed(name)).to_string();
+        let title = format!("{}{name}", self.title_prefix);
+        let img = match (image, id) {
+            (Some(image), _) => ImageSource::url(image.as_ref()).ok(),
+            (None, Some(img_id)) => assets
+                .url("clow-card", &[("id", &img_id.to_string()), ("name", name)])
+                .and_then(|url| ImageSource::url(url).ok()),
+            (None, None) => None,
         };
 
-        let custom_id = CustomId::ButtonClowcardInfo(Cow::Borrowed(name)).to_string();
-        let title = format!("The {name}");
-        let img = ImageSource::url(format!(
-            "https://cdn.discordapp.com/attachments/953801841412538368/{img_id}/The{name}.jpg"
-        ))
-        .expect("valid img url");
-
-        let embed = EmbedBuilder::new()
+        let mut embed = EmbedBuilder::new()
             .title(&title)
             .description(meaning.as_ref())
-            .thumbnail(img)
-            .color(color::PRIMARY)
-            .build();
+            .color(config.colors.primary);
+        if let Some(img) = img {
+            embed = embed.thumbnail(img);
+        }
         let component = Component::Button(Button {
             custom_id: Some(custom_id),
             disabled: false,
-            emoji: Some(EMOJI_MAGICBOOK),
+            emoji: Some(assets.emoji("magicbook", "📖").reaction()),
             label: Some(title),
             style: ButtonStyle::Secondary,
             url: None,
             sku_id: None,
         });
-        (embed, component)
+        (embed.build(), component)
     }
     fn long(ClowCard { full, .. }: &ClowCard) -> &str {
         full
     }
-    pub fn long_by_name(name: &str) -> Option<&'static str> {
-        let deck = Self::get_instance();
-        let pos = deck
+    pub fn long_by_name(&self, name: &str) -> Option<&str> {
+        let pos = self
             .binary_search_by(|card| card.name.as_ref().cmp(name))
             .ok()?;
-        Some(Self::long(&deck.0[pos]))
+        Some(Self::long(&self.cards[pos]))
     }
 }
 
@@ -164,6 +288,20 @@ impl Deref for ClowCardDeck {
     type Target = Inner;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.cards
+    }
+}
+
+impl Serialize for ClowCardDeck {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.cards.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ClowCardDeck {
+    /// Stored decks go through the same validation as uploaded ones
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let cards = Vec::deserialize(deserializer)?;
+        Self::new(cards, "").map_err(serde::de::Error::custom)
     }
 }
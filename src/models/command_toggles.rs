@@ -0,0 +1,99 @@
+use crate::commands::Marker;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use twilight_model::id::{marker::ChannelMarker, Id};
+
+/// Commands a guild turned off, keyed by the primary command name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandToggles {
+    /// Off in the whole guild
+    pub disabled: BTreeSet<String>,
+    /// Per channel overrides, `true` turns on a command that is off in the guild
+    pub channels: BTreeMap<Id<ChannelMarker>, BTreeMap<String, bool>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Toggle {
+    On,
+    Off,
+    /// Drop the channel override, or turn the command back on in the guild
+    Inherit,
+}
+
+impl CommandToggles {
+    pub fn is_empty(&self) -> bool {
+        self.disabled.is_empty() && self.channels.is_empty()
+    }
+
+    pub fn is_enabled(&self, channel: Option<Id<ChannelMarker>>, marker: Marker) -> bool {
+        let name = marker.name();
+        channel
+            .and_then(|channel| self.channels.get(&channel)?.get(name).copied())
+            .unwrap_or_else(|| !self.disabled.contains(name))
+    }
+
+    pub fn set(&mut self, channel: Option<Id<ChannelMarker>>, marker: Marker, toggle: Toggle) {
+        let name = marker.name().to_owned();
+        match (channel, toggle) {
+            (None, Toggle::Off) => {
+                self.disabled.insert(name);
+            }
+            (None, Toggle::On | Toggle::Inherit) => {
+                self.disabled.remove(&name);
+            }
+            (Some(channel), Toggle::On | Toggle::Off) => {
+                self.channels
+                    .entry(channel)
+                    .or_default()
+                    .insert(name, toggle == Toggle::On);
+            }
+            (Some(channel), Toggle::Inherit) => {
+                if let Some(overrides) = self.channels.get_mut(&channel) {
+                    overrides.remove(&name);
+                    if overrides.is_empty() {
+                        self.channels.remove(&channel);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_overrides_guild() {
+        let (here, there) = (Some(Id::new(1)), Some(Id::new(2)));
+        let mut toggles = CommandToggles::default();
+        assert!(toggles.is_enabled(here, Marker::Dice));
+
+        toggles.set(None, Marker::Dice, Toggle::Off);
+        toggles.set(here, Marker::Dice, Toggle::On);
+        assert!(toggles.is_enabled(here, Marker::Dice));
+        assert!(!toggles.is_enabled(there, Marker::Dice));
+        assert!(!toggles.is_enabled(None, Marker::Dice));
+        assert!(toggles.is_enabled(there, Marker::Help));
+
+        // Turned off in a channel while on in the guild
+        toggles.set(None, Marker::Dice, Toggle::Inherit);
+        toggles.set(there, Marker::Dice, Toggle::Off);
+        assert!(toggles.is_enabled(None, Marker::Dice));
+        assert!(!toggles.is_enabled(there, Marker::Dice));
+    }
+
+    #[test]
+    fn inherit_drops_overrides() {
+        let here = Some(Id::new(1));
+        let mut toggles = CommandToggles::default();
+        toggles.set(here, Marker::Dice, Toggle::Off);
+        toggles.set(None, Marker::Dice, Toggle::Off);
+        toggles.set(here, Marker::Dice, Toggle::Inherit);
+        assert!(!toggles.is_enabled(here, Marker::Dice));
+        toggles.set(None, Marker::Dice, Toggle::On);
+        assert!(toggles.is_enabled(here, Marker::Dice));
+        assert!(toggles.is_empty());
+    }
+}
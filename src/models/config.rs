@@ -0,0 +1,495 @@
+use crate::commands::Marker;
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
+use tracing_subscriber::EnvFilter;
+use twilight_gateway::Intents;
+use twilight_model::id::{
+    marker::{ChannelMarker, GuildMarker, UserMarker, WebhookMarker},
+    Id,
+};
+
+/// Read when `--config` is not given, it is fine for it to be missing
+pub const DEFAULT_PATH: &str = "boothebot.toml";
+/// `BOOTHEBOT_LIMITS__MAX_DICE=50` overrides `limits.max_dice`
+const ENV_PREFIX: &str = "BOOTHEBOT_";
+
+/// Runtime settings, layered as defaults < TOML file < environment < CLI flags
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub discord: Discord,
+    pub limits: Limits,
+    pub colors: Colors,
+    pub time: Time,
+    pub paths: Paths,
+    pub shutdown: Shutdown,
+    pub server: Server,
+    pub log: Log,
+    pub report: Report,
+    pub cooldown: Cooldown,
+    pub admin: Admin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Discord {
+    /// Gateway intent names, e.g. `GUILD_MESSAGES`
+    pub intents: Vec<String>,
+    /// Register commands to this guild only, for development
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dev_guild: Option<Id<GuildMarker>>,
+    /// Send HTTP requests over plain http to this host, e.g. an http-proxy or a mock server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    pub max_dice: u8,
+    pub max_clow: u8,
+    pub max_runes: u8,
+    pub max_guild_books: usize,
+    pub max_guild_decks: usize,
+    /// Bytes
+    pub max_upload_size: u64,
+    /// Give up downloading an upload after this long
+    pub upload_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Colors {
+    pub primary: u32,
+    pub error: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Time {
+    /// Daily readings roll over at midnight of this UTC offset
+    pub utc_offset_minutes: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Paths {
+    pub storage: PathBuf,
+    /// Gateway session saved on shutdown and resumed on the next start
+    pub session: PathBuf,
+    /// Overrides the embedded datasets, same as `BOOTHEBOT_DATA_DIR`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Shutdown {
+    /// How long in-flight handlers and the gateway close may take, each
+    pub drain_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Server {
+    /// Serve `/metrics`, `/healthz` and `/readyz` on this address, disabled when missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub listen: Option<SocketAddr>,
+    /// `/healthz` fails once the gateway has been disconnected this long
+    pub unhealthy_after_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Log {
+    pub format: LogFormat,
+    /// `EnvFilter` directives, `RUST_LOG` takes precedence when set
+    pub filter: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One line per event
+    #[default]
+    Text,
+    /// Multi-line, for reading locally
+    Pretty,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Report {
+    /// Post error reports to this channel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<Id<ChannelMarker>>,
+    /// Or execute this webhook, `https://discord.com/api/webhooks/<id>/<token>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+    /// Reports beyond this are dropped
+    pub max_per_minute: u32,
+    /// The same error is reported again only after this long
+    pub dedup_window_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cooldown {
+    pub user: Rate,
+    pub channel: Rate,
+    /// Extra limit per user for these commands, keyed by command name
+    pub commands: BTreeMap<String, Rate>,
+    /// React to rate limited message commands instead of ignoring them
+    pub react: bool,
+    /// Never limited, along with the owner and guild administrators using slash commands
+    pub bypass: Vec<Id<UserMarker>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Admin {
+    /// Private guild where `/admin` is registered, disabled when missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild: Option<Id<GuildMarker>>,
+    /// Allowed to use `/admin`, along with the application owner
+    pub owners: Vec<Id<UserMarker>>,
+}
+
+/// Token bucket, `burst` uses at once then one more every `per_secs`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rate {
+    /// 0 disables the limit
+    pub burst: u32,
+    pub per_secs: f64,
+}
+
+impl Default for Discord {
+    fn default() -> Self {
+        Self {
+            intents: [
+                "GUILDS",
+                "GUILD_MESSAGES",
+                "DIRECT_MESSAGES",
+                "MESSAGE_CONTENT",
+            ]
+            .map(str::to_owned)
+            .into(),
+            dev_guild: None,
+            proxy: None,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_dice: 30,
+            max_clow: 5,
+            max_runes: 5,
+            max_guild_books: 10,
+            max_guild_decks: 10,
+            max_upload_size: 256 * 1024,
+            upload_timeout_secs: 10,
+        }
+    }
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Self {
+            primary: 0xccff77,
+            error: 0xff3355,
+        }
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        // GMT+7
+        Self {
+            utc_offset_minutes: 7 * 60,
+        }
+    }
+}
+
+impl Default for Paths {
+    fn default() -> Self {
+        Self {
+            storage: "data/storage.json".into(),
+            session: "data/session.json".into(),
+            data_dir: None,
+        }
+    }
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            unhealthy_after_secs: 60,
+        }
+    }
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            filter: "info".to_owned(),
+        }
+    }
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self {
+            channel: None,
+            webhook: None,
+            max_per_minute: 5,
+            dedup_window_secs: 3600,
+        }
+    }
+}
+
+impl Report {
+    /// Id and token of the webhook
+    pub fn webhook(&self) -> Option<(Id<WebhookMarker>, &str)> {
+        let path = self.webhook.as_deref()?.split_once("/webhooks/")?.1;
+        let (id, token) = path.split_once('/')?;
+        let token = token.trim_end_matches('/');
+        if token.is_empty() {
+            return None;
+        }
+        Some((id.parse().ok()?, token))
+    }
+}
+
+impl Default for Cooldown {
+    fn default() -> Self {
+        Self {
+            user: Rate {
+                burst: 5,
+                per_secs: 4.0,
+            },
+            channel: Rate {
+                burst: 20,
+                per_secs: 1.0,
+            },
+            commands: BTreeMap::new(),
+            react: true,
+            bypass: Vec::new(),
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self {
+            drain_timeout_secs: 10,
+        }
+    }
+}
+
+impl Discord {
+    pub fn intents(&self) -> Intents {
+        self.intents
+            .iter()
+            .filter_map(|name| Intents::from_name(name))
+            .collect()
+    }
+}
+
+impl Config {
+    /// Load `path` (or `boothebot.toml` when present), then apply the
+    /// `BOOTHEBOT_<SECTION>__<KEY>` environment variables and the `key=value` overrides,
+    /// nested keys take more `__`, e.g. `BOOTHEBOT_COOLDOWN__USER__BURST`
+    pub fn load(path: Option<&Path>, overrides: &[String]) -> Result<Self> {
+        // Layers are merged into the defaults, so a single nested key can be overridden
+        let mut table = Table::try_from(Self::default()).context("defaults should serialize")?;
+        let file = match path {
+            Some(path) => Some(Self::read(path)?),
+            None if Path::new(DEFAULT_PATH).exists() => Some(Self::read(Path::new(DEFAULT_PATH))?),
+            None => None,
+        };
+        if let Some(file) = file {
+            Self::merge(&mut table, file);
+        }
+        for (name, value) in std::env::vars() {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            // Other `BOOTHEBOT_` variables such as `BOOTHEBOT_DATA_DIR` are not settings
+            if !key.contains("__") {
+                continue;
+            }
+            let key = key.replace("__", ".").to_lowercase();
+            Self::set(&mut table, &key, &value).with_context(|| format!("invalid `{name}`"))?;
+        }
+        for pair in overrides {
+            let (key, value) = pair
+                .split_once('=')
+                .with_context(|| format!("`{pair}` should be in the form `key=value`"))?;
+            Self::set(&mut table, key.trim(), value.trim())?;
+        }
+
+        let config: Self = table.try_into().context("invalid config")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> Result<Table> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to read `{}`", path.display()))?;
+        toml::from_str(&raw).with_context(|| format!("`{}` should be valid TOML", path.display()))
+    }
+
+    /// Tables are merged key by key, other values are replaced
+    fn merge(table: &mut Table, layer: Table) {
+        for (key, value) in layer {
+            match (table.get_mut(&key), value) {
+                (Some(Value::Table(table)), Value::Table(layer)) => Self::merge(table, layer),
+                (_, value) => {
+                    table.insert(key, value);
+                }
+            }
+        }
+    }
+
+    /// Set a dotted key such as `section.field`,
+    /// the value is parsed as TOML and kept as a string otherwise
+    fn set(table: &mut Table, key: &str, raw: &str) -> Result<()> {
+        let Some((path, field)) = key.rsplit_once('.') else {
+            bail!("`{key}` should be in the form `section.field`");
+        };
+        let value = toml::from_str::<Table>(&format!("value = {raw}"))
+            .ok()
+            .and_then(|mut parsed| parsed.remove("value"))
+            .unwrap_or_else(|| Value::String(raw.to_owned()));
+        let mut table = table;
+        for section in path.split('.') {
+            table = table
+                .entry(section)
+                .or_insert_with(|| Value::Table(Table::new()))
+                .as_table_mut()
+                .with_context(|| format!("`{section}` in `{key}` should be a table"))?;
+        }
+        table.insert(field.to_owned(), value);
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        for name in &self.discord.intents {
+            ensure!(
+                Intents::from_name(name).is_some(),
+                "unknown intent `{name}`"
+            );
+        }
+        let Limits {
+            max_dice,
+            max_clow,
+            max_runes,
+            max_guild_books,
+            max_guild_decks,
+            max_upload_size,
+            upload_timeout_secs,
+        } = self.limits;
+        // `~dice` accepts up to 100 dice
+        ensure!(
+            (1..=100).contains(&max_dice),
+            "`limits.max_dice` should be 1 to 100"
+        );
+        // Each drawn card or rune gets a button in a single action row
+        ensure!(
+            (1..=5).contains(&max_clow),
+            "`limits.max_clow` should be 1 to 5"
+        );
+        ensure!(
+            (1..=5).contains(&max_runes),
+            "`limits.max_runes` should be 1 to 5"
+        );
+        // Autocomplete shows at most 25 choices
+        ensure!(
+            (1..=25).contains(&max_guild_books) && (1..=25).contains(&max_guild_decks),
+            "`limits.max_guild_books` and `limits.max_guild_decks` should be 1 to 25"
+        );
+        ensure!(
+            max_upload_size > 0,
+            "`limits.max_upload_size` should be positive"
+        );
+        ensure!(
+            upload_timeout_secs > 0,
+            "`limits.upload_timeout_secs` should be positive"
+        );
+        for (name, color) in [
+            ("primary", self.colors.primary),
+            ("error", self.colors.error),
+        ] {
+            ensure!(color <= 0xffffff, "`colors.{name}` should be a RGB color");
+        }
+        ensure!(
+            (-12 * 60..=14 * 60).contains(&self.time.utc_offset_minutes),
+            "`time.utc_offset_minutes` should be between -720 and 840"
+        );
+        ensure!(
+            self.report.webhook.is_none() || self.report.webhook().is_some(),
+            "`report.webhook` should be a Discord webhook URL"
+        );
+        let rates = [
+            ("user", &self.cooldown.user),
+            ("channel", &self.cooldown.channel),
+        ];
+        let commands = self
+            .cooldown
+            .commands
+            .iter()
+            .map(|(name, rate)| (&**name, rate));
+        for (name, rate) in rates.into_iter().chain(commands) {
+            ensure!(
+                rate.per_secs > 0.0 && rate.per_secs.is_finite(),
+                "`per_secs` of the `{name}` cooldown should be positive"
+            );
+        }
+        for name in self.cooldown.commands.keys() {
+            ensure!(
+                name.parse::<Marker>()
+                    .is_ok_and(|marker| marker.name() == name),
+                "unknown command `{name}` in `cooldown.commands`"
+            );
+        }
+        EnvFilter::try_new(&self.log.filter).context("`log.filter` should be valid directives")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_nested_keys() {
+        let mut table = Table::try_from(Config::default()).unwrap();
+        Config::set(&mut table, "limits.max_dice", "50").unwrap();
+        Config::set(&mut table, "cooldown.user.burst", "3").unwrap();
+        Config::set(&mut table, "cooldown.commands.dice.burst", "1").unwrap();
+        Config::set(&mut table, "cooldown.commands.dice.per_secs", "10.0").unwrap();
+        let config: Config = table.try_into().unwrap();
+        assert_eq!(config.limits.max_dice, 50);
+        assert_eq!(config.cooldown.user.burst, 3);
+        assert_eq!(config.cooldown.user.per_secs, 4.0);
+        assert_eq!(config.cooldown.commands["dice"].burst, 1);
+
+        let mut table = Table::new();
+        assert!(Config::set(&mut table, "limits", "1").is_err());
+        Config::set(&mut table, "limits.max_dice", "50").unwrap();
+        assert!(Config::set(&mut table, "limits.max_dice.value", "1").is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use super::config::{Cooldown, Rate};
+use crate::commands::Marker;
+use rustc_hash::FxHashMap;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use twilight_model::id::{
+    marker::{ChannelMarker, UserMarker},
+    Id,
+};
+
+/// Full buckets are dropped once this many are tracked
+const PRUNE_AT: usize = 10_000;
+
+/// Token buckets per user, per channel and per user and command
+#[derive(Debug, Default)]
+pub struct Cooldowns(Mutex<FxHashMap<Key, Bucket>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Key {
+    User(Id<UserMarker>),
+    Channel(Id<ChannelMarker>),
+    Command(Id<UserMarker>, Marker),
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, rate: &Rate, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed / rate.per_secs).min(f64::from(rate.burst));
+        self.updated = now;
+    }
+    /// Until one token is available
+    fn wait(&self, rate: &Rate) -> Duration {
+        Duration::from_secs_f64((1.0 - self.tokens).max(0.0) * rate.per_secs)
+    }
+}
+
+impl Cooldowns {
+    /// Take a token from every bucket the command uses,
+    /// or none of them and return how long to wait
+    pub fn check(
+        &self,
+        config: &Cooldown,
+        user: Id<UserMarker>,
+        channel: Option<Id<ChannelMarker>>,
+        marker: Marker,
+    ) -> Result<(), Duration> {
+        let limits = [
+            Some((Key::User(user), &config.user)),
+            channel.map(|channel| (Key::Channel(channel), &config.channel)),
+            config
+                .commands
+                .get(marker.name())
+                .map(|rate| (Key::Command(user, marker), rate)),
+        ];
+        let limits = limits
+            .into_iter()
+            .flatten()
+            // A burst of 0 disables the limit
+            .filter(|(_, rate)| rate.burst > 0);
+
+        let now = Instant::now();
+        let mut buckets = self.0.lock().unwrap();
+        if buckets.len() >= PRUNE_AT {
+            let max_per_secs = [&config.user, &config.channel]
+                .into_iter()
+                .chain(config.commands.values())
+                .map(|rate| rate.per_secs * f64::from(rate.burst))
+                .fold(0.0, f64::max);
+            buckets.retain(|_, bucket| {
+                now.duration_since(bucket.updated).as_secs_f64() < max_per_secs
+            });
+        }
+
+        let mut taken = Vec::with_capacity(3);
+        let mut wait = Duration::ZERO;
+        for (key, rate) in limits {
+            let bucket = buckets.entry(key).or_insert(Bucket {
+                tokens: f64::from(rate.burst),
+                updated: now,
+            });
+            bucket.refill(rate, now);
+            if bucket.tokens < 1.0 {
+                wait = wait.max(bucket.wait(rate));
+            }
+            taken.push(key);
+        }
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        for key in taken {
+            if let Some(bucket) = buckets.get_mut(&key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(burst: u32, per_secs: f64) -> Rate {
+        Rate { burst, per_secs }
+    }
+
+    fn config(user: Rate, channel: Rate) -> Cooldown {
+        Cooldown {
+            user,
+            channel,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn refill_is_capped_at_burst() {
+        let rate = rate(5, 2.0);
+        let now = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            updated: now,
+        };
+        bucket.refill(&rate, now + Duration::from_secs(3));
+        assert_eq!(bucket.tokens, 1.5);
+        assert_eq!(bucket.wait(&rate), Duration::ZERO);
+        bucket.tokens = 0.5;
+        assert_eq!(bucket.wait(&rate), Duration::from_secs(1));
+        bucket.refill(&rate, now + Duration::from_secs(60));
+        assert_eq!(bucket.tokens, 5.0);
+    }
+
+    #[test]
+    fn burst_then_wait() {
+        let cooldowns = Cooldowns::default();
+        let config = config(rate(2, 10.0), rate(0, 1.0));
+        let (user, channel) = (Id::new(1), Some(Id::new(2)));
+        for _ in 0..2 {
+            assert!(cooldowns
+                .check(&config, user, channel, Marker::Dice)
+                .is_ok());
+        }
+        let wait = cooldowns
+            .check(&config, user, channel, Marker::Dice)
+            .unwrap_err();
+        assert!(wait > Duration::from_secs(9) && wait <= Duration::from_secs(10));
+        // Other users have their own bucket
+        assert!(cooldowns
+            .check(&config, Id::new(3), channel, Marker::Dice)
+            .is_ok());
+    }
+
+    #[test]
+    fn rejected_checks_take_no_token() {
+        let cooldowns = Cooldowns::default();
+        let config = config(rate(1, 60.0), rate(1, 60.0));
+        let busy = Some(Id::new(10));
+        assert!(cooldowns
+            .check(&config, Id::new(1), busy, Marker::Dice)
+            .is_ok());
+        // The channel is empty, so the user bucket is left untouched
+        assert!(cooldowns
+            .check(&config, Id::new(2), busy, Marker::Dice)
+            .is_err());
+        assert!(cooldowns
+            .check(&config, Id::new(2), Some(Id::new(11)), Marker::Dice)
+            .is_ok());
+    }
+
+    #[test]
+    fn command_limit() {
+        let cooldowns = Cooldowns::default();
+        let mut config = config(rate(10, 1.0), rate(0, 1.0));
+        config
+            .commands
+            .insert(Marker::Dice.name().to_owned(), rate(1, 60.0));
+        let user = Id::new(1);
+        assert!(cooldowns.check(&config, user, None, Marker::Dice).is_ok());
+        assert!(cooldowns.check(&config, user, None, Marker::Dice).is_err());
+        assert!(cooldowns.check(&config, user, None, Marker::Ask).is_ok());
+    }
+}
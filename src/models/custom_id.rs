@@ -3,8 +3,12 @@ use serde::{Deserialize, Serialize};
 use std::{borrow::Cow, fmt::Display};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::enum_variant_names)]
 pub enum CustomId<'a> {
     ButtonClowcardInfo(Cow<'a, str>),
+    ButtonRuneInfo(Cow<'a, str>, bool),
+    /// Deck id and card name
+    ButtonCardInfo(Cow<'a, str>, Cow<'a, str>),
 }
 
 impl Display for CustomId<'_> {
@@ -0,0 +1,170 @@
+use crate::models::{
+    book_of_answers::BookOfAnswers, clow_cards::ClowCardDeck, horoscope::Horoscope,
+    i_ching::IChing, numerology::Numerology, runes::RuneSet,
+};
+use anyhow::{ensure, Context, Result};
+use arc_swap::ArcSwap;
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+};
+
+/// Directory whose files take precedence over the datasets compiled into the binary
+const DATA_DIR_ENV: &str = "BOOTHEBOT_DATA_DIR";
+/// Set from `paths.data_dir`, takes precedence over `DATA_DIR_ENV`
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// A dataset from `static/`, embedded at compile time
+pub struct StaticFile {
+    pub name: &'static str,
+    embedded: &'static str,
+}
+
+macro_rules! static_file {
+    ($name:literal) => {
+        StaticFile {
+            name: $name,
+            embedded: include_str!(concat!("../../static/", $name)),
+        }
+    };
+}
+
+pub const BOOK_OF_ANSWERS: StaticFile = static_file!("BookOfAnswers.json");
+pub const CLOW_CARDS: StaticFile = static_file!("ClowCardData.json");
+pub const RIDER_WAITE_MAJOR: StaticFile = static_file!("RiderWaiteMajor.json");
+pub const RIDER_WAITE_MINOR: StaticFile = static_file!("RiderWaiteMinor.json");
+pub const HOROSCOPE: StaticFile = static_file!("Horoscope.json");
+pub const I_CHING: StaticFile = static_file!("IChing.json");
+pub const NUMEROLOGY: StaticFile = static_file!("Numerology.json");
+pub const RUNES: StaticFile = static_file!("RuneData.json");
+pub const ABOUT: StaticFile = static_file!("about.md");
+pub const ASSETS: StaticFile = static_file!("assets.json");
+/// Text format of the book, only read from the override directory
+pub const LEGACY_BOOK_OF_ANSWERS: &str = "BookOfAnswers.txt";
+
+impl StaticFile {
+    /// The override file when present, otherwise the embedded copy
+    pub fn read(&self) -> Result<Cow<'static, str>> {
+        match override_path(self.name) {
+            Some(path) => std::fs::read_to_string(&path)
+                .map(Cow::Owned)
+                .with_context(|| format!("unable to read `{}`", path.display())),
+            None => Ok(Cow::Borrowed(self.embedded)),
+        }
+    }
+}
+
+/// `name` inside the override directory, if it exists there
+pub fn override_path(name: &str) -> Option<PathBuf> {
+    let path = override_target(name)?;
+    path.exists().then_some(path)
+}
+
+/// Where `name` would be read from when overridden, whether it exists or not
+pub fn override_target(name: &str) -> Option<PathBuf> {
+    let dir = match DATA_DIR.get() {
+        Some(dir) => dir.clone(),
+        None => std::env::var_os(DATA_DIR_ENV)?.into(),
+    };
+    Some(dir.join(name))
+}
+
+/// Use `dir` instead of `BOOTHEBOT_DATA_DIR`, must be called before any dataset is loaded
+pub fn set_data_dir(dir: PathBuf) {
+    let _ = DATA_DIR.set(dir);
+}
+
+/// Load every dataset so a malformed one is reported before connecting
+pub fn init() -> Result<()> {
+    BookOfAnswers::reloadable().init()?;
+    ClowCardDeck::reloadable().init()?;
+    About::reloadable().init()?;
+    Horoscope::init()?;
+    IChing::init()?;
+    Numerology::init()?;
+    RuneSet::init()?;
+    Ok(())
+}
+
+/// A dataset loaded on first use that can be replaced at runtime.
+///
+/// Readers keep the copy they loaded alive, so a reload never pulls data
+/// from under an in-flight response.
+pub struct Reloadable<T: 'static> {
+    name: &'static str,
+    load: fn() -> Result<T>,
+    current: OnceLock<ArcSwap<T>>,
+}
+
+impl<T> Reloadable<T> {
+    pub const fn new(name: &'static str, load: fn() -> Result<T>) -> Self {
+        Self {
+            name,
+            load,
+            current: OnceLock::new(),
+        }
+    }
+    fn current(&self) -> &ArcSwap<T> {
+        self.current.get_or_init(|| {
+            let data = (self.load)()
+                .unwrap_or_else(|err| panic!("{} should be in correct format: {err:#}", self.name));
+            ArcSwap::from_pointee(data)
+        })
+    }
+    pub fn get(&self) -> Arc<T> {
+        self.current().load_full()
+    }
+    /// Load and validate a fresh copy without publishing it
+    pub fn prepare(&self) -> Result<T> {
+        (self.load)().with_context(|| format!("invalid {}", self.name))
+    }
+    /// Load now unless already loaded, returning the error instead of panicking
+    pub fn init(&self) -> Result<()> {
+        if self.current.get().is_none() {
+            let _ = self.current.set(ArcSwap::from_pointee(self.prepare()?));
+        }
+        Ok(())
+    }
+    pub fn store(&self, data: T) {
+        self.current().store(Arc::new(data));
+    }
+}
+
+pub struct About;
+
+impl About {
+    fn reloadable() -> &'static Reloadable<Box<str>> {
+        static INSTANCE: Reloadable<Box<str>> = Reloadable::new("About", About::load);
+        &INSTANCE
+    }
+    fn load() -> Result<Box<str>> {
+        Self::from_text(&ABOUT.read()?)
+    }
+    pub fn from_text(text: &str) -> Result<Box<str>> {
+        // Shown as an embed description
+        ensure!(
+            (1..=4096).contains(&text.chars().count()),
+            "about text should have 1 to 4096 characters"
+        );
+        Ok(text.into())
+    }
+    pub fn text() -> Arc<Box<str>> {
+        Self::reloadable().get()
+    }
+}
+
+/// Reload every reloadable dataset from disk.
+///
+/// All datasets are validated before any of them is replaced,
+/// so a single bad file keeps the whole old copy in use.
+pub fn reload() -> Result<()> {
+    let book = BookOfAnswers::reloadable().prepare()?;
+    let decks = ClowCardDeck::reloadable().prepare()?;
+    let about = About::reloadable().prepare()?;
+
+    BookOfAnswers::reloadable().store(book);
+    ClowCardDeck::reloadable().store(decks);
+    About::reloadable().store(about);
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use super::{app_state::AppState, config::Report};
+use crate::commands::Marker;
+use rustc_hash::FxHashMap;
+use std::{
+    error::Error,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+use tracing::warn;
+use twilight_model::{channel::message::Embed, util::Timestamp};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+
+/// Longest input quoted in a report
+const MAX_INPUT_LEN: usize = 500;
+/// Discord limits embed descriptions to 4096 characters
+const MAX_CHAIN_LEN: usize = 4000;
+
+/// An error or panic to be posted to `report.channel` or `report.webhook`
+#[derive(Debug)]
+pub struct ErrorReport {
+    /// What failed, e.g. `message command`
+    pub context: &'static str,
+    pub command: Option<Marker>,
+    pub input: Option<String>,
+    /// The error followed by its sources
+    pub chain: Vec<String>,
+}
+
+/// Rate limits and deduplicates reports
+#[derive(Debug, Default)]
+pub struct ErrorReporter(Mutex<Limiter>);
+
+#[derive(Debug, Default)]
+struct Limiter {
+    window_start: Option<Instant>,
+    sent: u32,
+    seen: FxHashMap<String, Seen>,
+}
+
+#[derive(Debug, Default)]
+struct Seen {
+    last_sent: Option<Instant>,
+    /// Dropped since `last_sent`
+    suppressed: u32,
+}
+
+impl ErrorReport {
+    pub fn new(context: &'static str, err: &(dyn Error + 'static)) -> Self {
+        let chain = std::iter::successors(Some(err), |&err| err.source())
+            .map(ToString::to_string)
+            .collect();
+        Self {
+            context,
+            command: None,
+            input: None,
+            chain,
+        }
+    }
+
+    /// Only for panics, which carry a message instead of an error
+    pub fn panic(message: String) -> Self {
+        Self {
+            context: "handler panicked",
+            command: None,
+            input: None,
+            chain: vec![message],
+        }
+    }
+
+    pub fn with_input(mut self, command: Option<Marker>, input: String) -> Self {
+        self.command = command;
+        self.input = Some(input);
+        self
+    }
+
+    /// Reports of the same failure share a key
+    fn key(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.context,
+            self.command.map_or("", Marker::name),
+            self.chain.first().map_or("", String::as_str)
+        )
+    }
+
+    /// Post in the background, dropped when no destination is configured or when limited
+    pub fn send(self, state: &AppState) {
+        let config = &state.config.report;
+        if config.channel.is_none() && config.webhook.is_none() {
+            return;
+        }
+        let Some(suppressed) = state.reporter.admit(&self.key(), config) else {
+            return;
+        };
+        let embed = self.embed(state, suppressed);
+        let state = state.clone();
+        tokio::spawn(async move {
+            let _ = post(&state, embed)
+                .await
+                .inspect_err(|err| warn!(?err, "unable to post error report"));
+        });
+    }
+
+    fn embed(self, state: &AppState, suppressed: u32) -> Embed {
+        let mut chain = sanitize(&self.chain.join("\n↳ "));
+        truncate(&mut chain, MAX_CHAIN_LEN);
+        let mut embed = EmbedBuilder::new()
+            .title(format!("Lỗi: {}", self.context))
+            .description(format!("```\n{chain}\n```"))
+            .color(state.config.colors.error);
+        if let Some(command) = self.command {
+            embed = embed.field(EmbedFieldBuilder::new("Lệnh", command.name()).inline());
+        }
+        if let Some(input) = self.input {
+            let mut input = sanitize(&input);
+            truncate(&mut input, MAX_INPUT_LEN);
+            embed = embed.field(EmbedFieldBuilder::new(
+                "Nội dung",
+                format!("```\n{input}\n```"),
+            ));
+        }
+        if suppressed > 0 {
+            embed = embed.footer(EmbedFooterBuilder::new(format!(
+                "Đã bỏ qua {suppressed} lỗi tương tự"
+            )));
+        }
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        if let Ok(timestamp) = Timestamp::from_secs(now.as_secs() as i64) {
+            embed = embed.timestamp(timestamp);
+        }
+        embed.build()
+    }
+}
+
+impl ErrorReporter {
+    /// `None` when the report should be dropped,
+    /// otherwise how many of the same were dropped since it was last sent
+    fn admit(&self, key: &str, config: &Report) -> Option<u32> {
+        let mut limiter = self.0.lock().unwrap();
+        let dedup_window = Duration::from_secs(config.dedup_window_secs);
+
+        if limiter
+            .window_start
+            .is_none_or(|start| start.elapsed() >= Duration::from_secs(60))
+        {
+            limiter.window_start = Some(Instant::now());
+            limiter.sent = 0;
+            limiter.seen.retain(|_, seen| {
+                seen.last_sent
+                    .is_some_and(|last| last.elapsed() < dedup_window)
+            });
+        }
+
+        let rate_limited = limiter.sent >= config.max_per_minute;
+        let seen = limiter.seen.entry(key.to_owned()).or_default();
+        if rate_limited
+            || seen
+                .last_sent
+                .is_some_and(|last| last.elapsed() < dedup_window)
+        {
+            seen.suppressed += 1;
+            return None;
+        }
+        seen.last_sent = Some(Instant::now());
+        let suppressed = std::mem::take(&mut seen.suppressed);
+        limiter.sent += 1;
+        Some(suppressed)
+    }
+}
+
+async fn post(state: &AppState, embed: Embed) -> anyhow::Result<()> {
+    let embeds = [embed];
+    if let Some((id, token)) = state.config.report.webhook() {
+        state.bot.execute_webhook(id, token).embeds(&embeds).await?;
+    } else if let Some(channel) = state.config.report.channel {
+        state.bot.create_message(channel).embeds(&embeds).await?;
+    }
+    Ok(())
+}
+
+/// Keep user input from closing the code block
+fn sanitize(text: &str) -> String {
+    text.replace("```", "`\u{200b}`\u{200b}`")
+}
+
+fn truncate(text: &mut String, max_len: usize) {
+    if let Some((idx, _)) = text.char_indices().nth(max_len) {
+        text.truncate(idx);
+        text.push('…');
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::warn;
+use twilight_gateway::{Session, Shard};
+
+/// What a shard needs to resume its session after a restart
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GatewaySession {
+    pub session: Session,
+    pub resume_url: Option<String>,
+}
+
+impl GatewaySession {
+    pub fn capture(shard: &Shard) -> Option<Self> {
+        Some(Self {
+            session: shard.session()?.clone(),
+            resume_url: shard.resume_url().map(str::to_owned),
+        })
+    }
+
+    /// Take the saved session, the file is removed so a stale session is only tried once
+    pub fn take(path: &Path) -> Option<Self> {
+        let raw = std::fs::read_to_string(path).ok()?;
+        let _ = std::fs::remove_file(path)
+            .inspect_err(|err| warn!(?err, "unable to remove saved gateway session"));
+        serde_json::from_str(&raw)
+            .inspect_err(|err| warn!(?err, "saved gateway session is malformed"))
+            .ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Gateway and startup state behind `/healthz` and `/readyz`
+#[derive(Debug)]
+pub struct Health {
+    commands_registered: AtomicBool,
+    /// `None` while connected, starts out disconnected
+    disconnected_since: Mutex<Option<Instant>>,
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Self {
+            commands_registered: AtomicBool::new(false),
+            disconnected_since: Mutex::new(Some(Instant::now())),
+        }
+    }
+
+    pub fn commands_registered(&self) {
+        self.commands_registered.store(true, Ordering::Relaxed);
+    }
+
+    /// On `Ready`, or `Resumed` which replaces it when a saved session is resumed
+    pub fn connected(&self) {
+        *self.disconnected_since.lock().unwrap() = None;
+    }
+
+    /// Keeps the earliest time when called again before reconnecting
+    pub fn disconnected(&self) {
+        self.disconnected_since
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Instant::now);
+    }
+
+    pub fn disconnected_for(&self) -> Option<Duration> {
+        self.disconnected_since
+            .lock()
+            .unwrap()
+            .map(|since| since.elapsed())
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.commands_registered.load(Ordering::Relaxed) && self.disconnected_for().is_none()
+    }
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,80 @@
+use crate::models::{
+    config::Config,
+    data_registry::HOROSCOPE,
+    seed_generator::{SeedGenerator, TimeHash},
+    zodiac::{Zodiac, ZodiacInfo},
+};
+use anyhow::{Context, Result};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Deserialize;
+use std::sync::OnceLock;
+use twilight_model::channel::message::Embed;
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+
+#[derive(Debug, Deserialize)]
+pub struct Horoscope {
+    overview: Box<[Box<str>]>,
+    love: Box<[Box<str>]>,
+    work: Box<[Box<str>]>,
+    advice: Box<[Box<str>]>,
+    colors: Box<[Box<str>]>,
+}
+
+static INSTANCE: OnceLock<Horoscope> = OnceLock::new();
+
+impl Horoscope {
+    fn get_instance() -> &'static Self {
+        INSTANCE.get_or_init(|| {
+            Self::load()
+                .unwrap_or_else(|err| panic!("Horoscope should be in correct format: {err:#}"))
+        })
+    }
+    /// Load eagerly so a malformed corpus is reported at startup
+    pub fn init() -> Result<()> {
+        if INSTANCE.get().is_none() {
+            let _ = INSTANCE.set(Self::load()?);
+        }
+        Ok(())
+    }
+    fn load() -> Result<Self> {
+        Self::from_json(&HOROSCOPE.read()?).context("invalid Horoscope")
+    }
+    pub fn from_json(raw: &str) -> Result<Self> {
+        Ok(serde_json::from_str(raw)?)
+    }
+
+    /// Daily reading, identical for everyone sharing the same sign
+    pub fn embed(config: &Config, sign: Zodiac) -> Embed {
+        let corpus = Self::get_instance();
+        let seed = SeedGenerator::default()
+            .hash_time(TimeHash::Day(config.time.utc_offset_minutes))
+            .hash(sign)
+            .finish();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut pick = |phrases: &'static [Box<str>]| -> &'static str {
+            phrases
+                .choose(&mut rng)
+                .expect("Horoscope sections should not be empty")
+        };
+
+        let overview = pick(&corpus.overview);
+        let love = pick(&corpus.love);
+        let work = pick(&corpus.work);
+        let advice = pick(&corpus.advice);
+        let lucky_color = pick(&corpus.colors);
+        let lucky_number: u8 = rng.gen_range(1..=99);
+        let ZodiacInfo { dates, .. } = sign.info();
+
+        EmbedBuilder::new()
+            .title(format!("[ {sign} ]"))
+            .description(overview)
+            .field(EmbedFieldBuilder::new("Tình cảm", love))
+            .field(EmbedFieldBuilder::new("Công việc", work))
+            .field(EmbedFieldBuilder::new("Lời khuyên", advice))
+            .field(EmbedFieldBuilder::new("Màu may mắn", lucky_color).inline())
+            .field(EmbedFieldBuilder::new("Số may mắn", lucky_number.to_string()).inline())
+            .footer(EmbedFooterBuilder::new(*dates))
+            .color(config.colors.primary)
+            .build()
+    }
+}
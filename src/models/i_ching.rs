@@ -0,0 +1,216 @@
+use crate::models::{
+    config::Config,
+    data_registry::I_CHING,
+    seed_generator::{SeedGenerator, TimeHash},
+};
+use anyhow::{ensure, Context, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Deserialize;
+use std::{borrow::Cow, fmt::Write, sync::OnceLock};
+use twilight_model::{
+    channel::message::Embed,
+    id::{marker::UserMarker, Id},
+};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
+
+/// Hexagrams indexed by their line pattern, bit `i` is the `i`-th line from the bottom
+type Inner = Box<[Hexagram]>;
+
+pub struct IChing(Inner);
+
+pub struct Hexagram {
+    number: u32,
+    name: Box<str>,
+    judgment: Box<str>,
+    changing: Box<[Box<str>]>,
+}
+
+#[derive(Deserialize)]
+struct HexagramCow<'a> {
+    number: u32,
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+    #[serde(borrow)]
+    lines: Cow<'a, str>,
+    #[serde(borrow)]
+    judgment: Cow<'a, str>,
+    #[serde(borrow)]
+    changing: Vec<Cow<'a, str>>,
+}
+
+/// A line cast with three coins, the value is the sum of the coins
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line {
+    OldYin = 6,
+    YoungYang = 7,
+    YoungYin = 8,
+    OldYang = 9,
+}
+
+impl Line {
+    fn cast(rng: &mut impl Rng) -> Self {
+        // Heads count as 3, tails as 2
+        let sum: u8 = (0..3).map(|_| if rng.gen_bool(0.5) { 3 } else { 2 }).sum();
+        match sum {
+            6 => Line::OldYin,
+            7 => Line::YoungYang,
+            8 => Line::YoungYin,
+            _ => Line::OldYang,
+        }
+    }
+    fn is_yang(self) -> bool {
+        matches!(self, Line::YoungYang | Line::OldYang)
+    }
+    fn is_changing(self) -> bool {
+        matches!(self, Line::OldYin | Line::OldYang)
+    }
+    fn glyph(self) -> &'static str {
+        match self {
+            Line::OldYin => "━━━   ━━━ ×",
+            Line::YoungYang => "━━━━━━━━━",
+            Line::YoungYin => "━━━   ━━━",
+            Line::OldYang => "━━━━━━━━━ ○",
+        }
+    }
+}
+
+static INSTANCE: OnceLock<IChing> = OnceLock::new();
+
+impl IChing {
+    fn get_instance() -> &'static Self {
+        INSTANCE.get_or_init(|| {
+            Self::load().unwrap_or_else(|err| panic!("IChing should be in correct format: {err:#}"))
+        })
+    }
+    /// Load eagerly so a malformed hexagram table is reported at startup
+    pub fn init() -> Result<()> {
+        if INSTANCE.get().is_none() {
+            let _ = INSTANCE.set(Self::load()?);
+        }
+        Ok(())
+    }
+    fn load() -> Result<Self> {
+        Self::from_json(&I_CHING.read()?).context("invalid IChing")
+    }
+    pub fn from_json(raw: &str) -> Result<Self> {
+        let mut hexagrams = serde_json::from_str::<Vec<HexagramCow>>(raw)?
+            .into_iter()
+            .map(|hexagram| {
+                let pattern = hexagram
+                    .lines
+                    .bytes()
+                    .rev()
+                    .fold(0, |acc, bit| acc << 1 | usize::from(bit == b'1'));
+                (pattern, hexagram)
+            })
+            .collect::<Vec<_>>();
+        hexagrams.sort_by_key(|(pattern, _)| *pattern);
+        ensure!(
+            hexagrams.iter().map(|(pattern, _)| *pattern).eq(0..64),
+            "IChing should contain each of the 64 hexagrams exactly once"
+        );
+
+        let hexagrams = hexagrams
+            .into_iter()
+            .map(
+                |(
+                    _,
+                    HexagramCow {
+                        number,
+                        name,
+                        judgment,
+                        changing,
+                        ..
+                    },
+                )| {
+                    ensure!(changing.len() == 6, "hexagram {number} needs 6 line texts");
+                    Ok(Hexagram {
+                        number,
+                        name: name.into(),
+                        judgment: judgment.into(),
+                        changing: changing.into_iter().map(Into::into).collect(),
+                    })
+                },
+            )
+            .collect::<Result<_>>()?;
+
+        Ok(IChing(hexagrams))
+    }
+    pub fn cast(config: &Config, content: Option<&str>, author: Id<UserMarker>) -> Vec<Embed> {
+        let book = Self::get_instance();
+        let time = match content {
+            None => TimeHash::Day(config.time.utc_offset_minutes),
+            Some(_) => TimeHash::Minute,
+        };
+        let state = SeedGenerator::default()
+            .hash_time(time)
+            .hash(author)
+            .hash(content)
+            .finish();
+        let mut rng = StdRng::seed_from_u64(state);
+
+        // Lines are cast from the bottom up
+        let lines: [Line; 6] = std::array::from_fn(|_| Line::cast(&mut rng));
+        let pattern = |yang: fn(Line) -> bool| {
+            lines
+                .iter()
+                .rev()
+                .fold(0, |acc, line| acc << 1 | usize::from(yang(*line)))
+        };
+        let primary = &book.0[pattern(Line::is_yang)];
+
+        let mut embeds = vec![Self::primary(config, primary, &lines)];
+        if lines.iter().any(|line| line.is_changing()) {
+            let relating = &book.0[pattern(|line| line.is_yang() ^ line.is_changing())];
+            embeds.push(Self::relating(config, relating));
+        }
+        embeds
+    }
+    fn primary(config: &Config, hexagram: &Hexagram, lines: &[Line; 6]) -> Embed {
+        let mut description = format!("{}\n```\n", hexagram.judgment);
+        lines.iter().rev().for_each(|line| {
+            let _ = writeln!(description, "{}", line.glyph());
+        });
+        description.push_str("```");
+
+        let embed = EmbedBuilder::new()
+            .title(format!("{} Quẻ {}", Self::glyph(hexagram), hexagram.name))
+            .description(description)
+            .color(config.colors.primary);
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.is_changing())
+            .fold(embed, |embed, (pos, line)| {
+                embed.field(EmbedFieldBuilder::new(
+                    Self::line_name(pos, *line),
+                    hexagram.changing[pos].as_ref(),
+                ))
+            })
+            .build()
+    }
+    fn relating(config: &Config, hexagram: &Hexagram) -> Embed {
+        EmbedBuilder::new()
+            .title(format!(
+                "{} Quẻ biến {}",
+                Self::glyph(hexagram),
+                hexagram.name
+            ))
+            .description(hexagram.judgment.as_ref())
+            .color(config.colors.primary)
+            .build()
+    }
+    fn glyph(hexagram: &Hexagram) -> char {
+        // Unicode hexagram symbols follow the King Wen sequence
+        char::from_u32(0x4DC0 + hexagram.number - 1).unwrap_or('☯')
+    }
+    /// Traditional line name, e.g. `Sơ Cửu` or `Lục Nhị`
+    fn line_name(pos: usize, line: Line) -> String {
+        const POSITIONS: [&str; 6] = ["Sơ", "Nhị", "Tam", "Tứ", "Ngũ", "Thượng"];
+        let kind = if line.is_yang() { "Cửu" } else { "Lục" };
+        match pos {
+            0 | 5 => format!("Hào {} {kind}", POSITIONS[pos]),
+            _ => format!("Hào {kind} {}", POSITIONS[pos]),
+        }
+    }
+}
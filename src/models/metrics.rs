@@ -0,0 +1,138 @@
+use crate::commands::Marker;
+use prometheus::{
+    core::Collector, histogram_opts, opts, Encoder, Gauge, HistogramTimer, HistogramVec,
+    IntCounterVec, Registry, TextEncoder,
+};
+use std::{collections::BTreeMap, time::Duration};
+use twilight_http::error::ErrorType;
+
+/// Prometheus metrics, served on `/metrics` when `server.listen` is set
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Registry,
+    commands: IntCounterVec,
+    latency: HistogramVec,
+    response_errors: IntCounterVec,
+    gateway_latency: Gauge,
+    gateway_reconnects: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("boothebot".to_owned()), None)
+            .expect("metric prefix should be valid");
+        let commands = IntCounterVec::new(
+            opts!("commands_total", "Commands handled"),
+            &["command", "path"],
+        )
+        .expect("metric should be valid");
+        let latency = HistogramVec::new(
+            histogram_opts!(
+                "command_duration_seconds",
+                "Time to build and send a command response"
+            ),
+            &["command", "path"],
+        )
+        .expect("metric should be valid");
+        let response_errors = IntCounterVec::new(
+            opts!(
+                "response_errors_total",
+                "Responses Discord failed to accept"
+            ),
+            &["path", "status"],
+        )
+        .expect("metric should be valid");
+        let gateway_latency = Gauge::new(
+            "gateway_latency_seconds",
+            "Latest gateway heartbeat round trip",
+        )
+        .expect("metric should be valid");
+        let gateway_reconnects = IntCounterVec::new(
+            opts!(
+                "gateway_reconnects_total",
+                "Gateway sessions started after the first one"
+            ),
+            &["kind"],
+        )
+        .expect("metric should be valid");
+
+        for collector in [
+            Box::new(commands.clone()) as Box<dyn Collector>,
+            Box::new(latency.clone()),
+            Box::new(response_errors.clone()),
+            Box::new(gateway_latency.clone()),
+            Box::new(gateway_reconnects.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric should be registered once");
+        }
+
+        Self {
+            registry,
+            commands,
+            latency,
+            response_errors,
+            gateway_latency,
+            gateway_reconnects,
+        }
+    }
+
+    /// Count a command, the latency is recorded when the timer drops
+    pub fn command(&self, command: Marker, path: &str) -> HistogramTimer {
+        let labels = [command.name(), path];
+        self.commands.with_label_values(&labels).inc();
+        self.latency.with_label_values(&labels).start_timer()
+    }
+
+    /// Commands handled since start by name, over every path
+    pub fn command_counts(&self) -> BTreeMap<String, u64> {
+        let mut counts = BTreeMap::new();
+        for family in self.commands.collect() {
+            for metric in family.get_metric() {
+                let Some(command) = metric
+                    .get_label()
+                    .iter()
+                    .find(|label| label.name() == "command")
+                else {
+                    continue;
+                };
+                *counts.entry(command.value().to_owned()).or_default() +=
+                    metric.get_counter().get_value() as u64;
+            }
+        }
+        counts
+    }
+
+    pub fn response_error(&self, path: &str, err: &twilight_http::Error) {
+        let status = match err.kind() {
+            ErrorType::Response { status, .. } => status.get().to_string(),
+            _ => "none".to_owned(),
+        };
+        self.response_errors
+            .with_label_values(&[path, status.as_str()])
+            .inc();
+    }
+
+    pub fn gateway_latency(&self, latency: Duration) {
+        self.gateway_latency.set(latency.as_secs_f64());
+    }
+
+    /// `kind` is `identify` or `resume`
+    pub fn gateway_reconnect(&self, kind: &str) {
+        self.gateway_reconnects.with_label_values(&[kind]).inc();
+    }
+
+    /// Text exposition format
+    pub fn encode(&self) -> String {
+        let mut buf = Vec::new();
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut buf);
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,8 +1,23 @@
-pub mod animated_emoji;
 pub mod app_state;
+pub mod assets;
+pub mod blocklist;
 pub mod book_of_answers;
 pub mod bot;
 pub mod clow_cards;
+pub mod command_toggles;
+pub mod config;
+pub mod cooldown;
 pub mod custom_id;
+pub mod data_registry;
+pub mod error_report;
+pub mod gateway_session;
+pub mod health;
+pub mod horoscope;
+pub mod i_ching;
+pub mod metrics;
+pub mod numerology;
 pub mod relationship_level;
+pub mod runes;
 pub mod seed_generator;
+pub mod storage;
+pub mod zodiac;
@@ -0,0 +1,185 @@
+use crate::models::{config::Config, data_registry::NUMEROLOGY, zodiac::BirthDate};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::sync::OnceLock;
+use twilight_model::channel::message::Embed;
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, EmbedFooterBuilder};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+type Inner = Box<[NumberMeaning]>;
+
+#[derive(Debug)]
+pub struct Numerology(Inner);
+
+#[derive(Debug, Deserialize)]
+pub struct NumberMeaning {
+    number: u32,
+    title: Box<str>,
+    meaning: Box<str>,
+}
+
+static INSTANCE: OnceLock<Numerology> = OnceLock::new();
+
+impl Numerology {
+    fn get_instance() -> &'static Self {
+        INSTANCE.get_or_init(|| {
+            Self::load()
+                .unwrap_or_else(|err| panic!("Numerology should be in correct format: {err:#}"))
+        })
+    }
+    /// Load eagerly so a malformed table is reported at startup
+    pub fn init() -> Result<()> {
+        if INSTANCE.get().is_none() {
+            let _ = INSTANCE.set(Self::load()?);
+        }
+        Ok(())
+    }
+    fn load() -> Result<Self> {
+        Self::from_json(&NUMEROLOGY.read()?).context("invalid Numerology")
+    }
+    pub fn from_json(raw: &str) -> Result<Self> {
+        let mut meanings = serde_json::from_str::<Vec<NumberMeaning>>(raw)?;
+        meanings.sort_by_key(|meaning| meaning.number);
+        Ok(Numerology(meanings.into()))
+    }
+    fn meaning(number: u32) -> Option<&'static NumberMeaning> {
+        let book = Self::get_instance();
+        let pos = book
+            .0
+            .binary_search_by_key(&number, |meaning| meaning.number)
+            .ok()?;
+        Some(&book.0[pos])
+    }
+
+    /// Sum the digits until a single digit or a master number (11, 22, 33) remains
+    pub fn reduce(mut number: u32) -> u32 {
+        while number > 9 && !matches!(number, 11 | 22 | 33) {
+            number = std::iter::successors(Some(number), |n| (*n >= 10).then_some(n / 10))
+                .map(|n| n % 10)
+                .sum();
+        }
+        number
+    }
+    pub fn life_path(date: BirthDate) -> Option<u32> {
+        let year = date.year()?;
+        let sum = Self::reduce(date.day().into())
+            + Self::reduce(date.month().into())
+            + Self::reduce(year.into());
+        Some(Self::reduce(sum))
+    }
+    pub fn expression(name: &str) -> u32 {
+        Self::reduce(Self::letters(name).map(Self::letter_value).sum())
+    }
+    pub fn soul_urge(name: &str) -> u32 {
+        Self::reduce(
+            Self::letters(name)
+                .filter(|c| Self::is_vowel(*c))
+                .map(Self::letter_value)
+                .sum(),
+        )
+    }
+    /// Compatibility of two life path numbers, returns a score (0-100) and a short verdict
+    pub fn compatibility(lhs: u32, rhs: u32) -> (u8, &'static str) {
+        // Master numbers behave like their root
+        let root = |n: u32| if n > 9 { n % 9 } else { n };
+        let group = |n: u32| match root(n) {
+            1 | 5 | 7 => 0,
+            2 | 4 | 8 => 1,
+            _ => 2,
+        };
+        match (root(lhs), root(rhs)) {
+            (l, r) if l == r => (90, "Cùng số chủ đạo, thấu hiểu nhau sâu sắc"),
+            (l, r) if group(l) == group(r) => (80, "Cùng nhóm số, hòa hợp tự nhiên"),
+            _ => (50, "Khác biệt, cần thêm thời gian để thấu hiểu"),
+        }
+    }
+
+    /// ASCII lowercase letters of the name, Vietnamese diacritics are stripped
+    fn letters(name: &str) -> impl Iterator<Item = char> + '_ {
+        name.nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .map(|c| match c {
+                'đ' | 'Đ' => 'd',
+                c => c.to_ascii_lowercase(),
+            })
+            .filter(char::is_ascii_lowercase)
+    }
+    fn letter_value(c: char) -> u32 {
+        // Pythagorean table: a=1, ..., i=9, j=1, ...
+        (c as u32 - 'a' as u32) % 9 + 1
+    }
+    fn is_vowel(c: char) -> bool {
+        // `y` is always a vowel in Vietnamese names (Vy, Thúy, ...)
+        matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+    }
+
+    pub fn embed(config: &Config, name: &str, date: BirthDate) -> Embed {
+        let numbers = [
+            ("Số chủ đạo", Self::life_path(date)),
+            ("Số biểu đạt", Some(Self::expression(name))),
+            ("Số linh hồn", Some(Self::soul_urge(name))),
+        ];
+
+        numbers
+            .into_iter()
+            .filter_map(|(label, number)| Some((label, Self::meaning(number?)?)))
+            .fold(
+                EmbedBuilder::new().title(format!("[ Thần số học: {name} ]")),
+                |embed,
+                 (
+                    label,
+                    NumberMeaning {
+                        number,
+                        title,
+                        meaning,
+                    },
+                )| {
+                    embed.field(EmbedFieldBuilder::new(
+                        format!("{label}: {number} - {title}"),
+                        meaning.as_ref(),
+                    ))
+                },
+            )
+            .footer(EmbedFooterBuilder::new(format!("Ngày sinh: {date}")))
+            .color(config.colors.primary)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduce_keeps_master_numbers() {
+        assert_eq!(Numerology::reduce(7), 7);
+        assert_eq!(Numerology::reduce(11), 11);
+        assert_eq!(Numerology::reduce(22), 22);
+        assert_eq!(Numerology::reduce(33), 33);
+        assert_eq!(Numerology::reduce(29), 11);
+        assert_eq!(Numerology::reduce(44), 8);
+        assert_eq!(Numerology::reduce(1987), 7);
+        assert_eq!(Numerology::reduce(99), 9);
+    }
+
+    #[test]
+    fn life_path() {
+        let date = BirthDate::new(11, 11).unwrap();
+        assert_eq!(Numerology::life_path(date), None);
+        // 11 + 11 + (1990 -> 19 -> 1) = 23 -> 5
+        assert_eq!(
+            Numerology::life_path(date.with_year(1990).unwrap()),
+            Some(5)
+        );
+        // 2 + 9 + (1974 -> 21 -> 3) = 14 -> 5
+        let date = BirthDate::new(2, 9).unwrap().with_year(1974).unwrap();
+        assert_eq!(Numerology::life_path(date), Some(5));
+    }
+
+    #[test]
+    fn names_strip_diacritics() {
+        assert_eq!(Numerology::expression("Đạt"), Numerology::expression("dat"));
+        // u = 3, y = 7
+        assert_eq!(Numerology::soul_urge("Thúy"), 1);
+    }
+}
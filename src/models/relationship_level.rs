@@ -1,19 +1,25 @@
-use crate::models::seed_generator::{SeedGenerator, TimeHash};
+use crate::models::{
+    assets::Assets,
+    config::Config,
+    numerology::Numerology,
+    seed_generator::{SeedGenerator, TimeHash},
+    zodiac::Zodiac,
+};
 use rand::{Rng, SeedableRng};
 use twilight_model::{
     channel::message::Embed,
-    id::{
-        marker::{EmojiMarker, UserMarker},
-        Id,
-    },
+    id::{marker::UserMarker, Id},
 };
-use twilight_util::builder::embed::{EmbedBuilder, ImageSource};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder, ImageSource};
 
 #[derive(Clone, Copy)]
 pub struct RelationshipLevel {
     pub title: &'static str,
     pub description: &'static str,
-    pub thumbnail: Id<EmojiMarker>,
+    /// Name in the asset manifest
+    pub thumbnail: &'static str,
+    /// Shown in the title when the thumbnail is not in the manifest
+    pub fallback: &'static str,
     pub color: u32,
     pub upperbound: f32,
 }
@@ -21,48 +27,60 @@ pub const RELATIONSHIP_LEVELS: &[RelationshipLevel] = &[
     RelationshipLevel {
         title: "[ Strangers in the Night ]",
         description: "Casual, brief encounter, no real connection.",
-        thumbnail: Id::new(1323549313430851594),
+        thumbnail: "relationship-strangers",
+        fallback: "🌙",
         color: 0xD3D3D3,
         upperbound: 20.,
     },
     RelationshipLevel {
         title: "[ Social Snackers ]",
         description: "Friendly, light connection, often in social settings.",
-        thumbnail: Id::new(1323551714887991296),
+        thumbnail: "relationship-social",
+        fallback: "🍿",
         color: 0xF1E2A7,
         upperbound: 50.,
     },
     RelationshipLevel {
         title: "[ Besties ]",
         description: "Solid friends, trust and fun, but not yet deeply emotional.",
-        thumbnail: Id::new(1323551637075398700),
+        thumbnail: "relationship-besties",
+        fallback: "🤝",
         color: 0x5D9BEC,
         upperbound: 75.,
     },
     RelationshipLevel {
         title: "[ Partners in Crime ]",
         description: "Strong bond, loyal and inseparable, lots of shared experiences.",
-        thumbnail: Id::new(1323547947601891370),
+        thumbnail: "relationship-partners",
+        fallback: "🕶️",
         color: 0xFF6F61,
         upperbound: 90.,
     },
     RelationshipLevel {
         title: "[ Soulmates Forever ]",
         description: "Deep connection, unspoken understanding, and long-term commitment.",
-        thumbnail: Id::new(1323550264824827924),
+        thumbnail: "relationship-soulmates",
+        fallback: "💞",
         color: 0x9B4D96,
         upperbound: 100.,
     },
 ];
 
 impl RelationshipLevel {
-    pub fn embed(user1: Id<UserMarker>, user2: Id<UserMarker>) -> Vec<Embed> {
+    pub fn embed(
+        config: &Config,
+        assets: &Assets,
+        user1: Id<UserMarker>,
+        user2: Id<UserMarker>,
+        zodiacs: Option<(Zodiac, Zodiac)>,
+        life_paths: Option<(u32, u32)>,
+    ) -> Vec<Embed> {
         const NUM_BOXES: usize = 20;
         const VAL_BOX: usize = 100 / NUM_BOXES;
         const EMPTY: &str = "";
 
         let seed = SeedGenerator::default()
-            .hash_time(TimeHash::Day)
+            .hash_time(TimeHash::Day(config.time.utc_offset_minutes))
             .hash(user1)
             .hash(user2)
             .finish();
@@ -74,6 +92,7 @@ impl RelationshipLevel {
             title,
             description,
             thumbnail,
+            fallback,
             color,
             ..
         } = RELATIONSHIP_LEVELS
@@ -86,16 +105,31 @@ impl RelationshipLevel {
             "{description}\n\
             ```css\n[{EMPTY:▣>num_fbox$}{EMPTY:▢>num_ebox$}] {percent:.2}%\n```"
         );
-        let thumbnail = ImageSource::url(format!(
-            "https://cdn.discordapp.com/emojis/{thumbnail}.webp"
-        ))
-        .unwrap();
+        let thumbnail = assets
+            .custom(thumbnail, &mut rng)
+            .and_then(|emoji| ImageSource::url(emoji.url()).ok());
+
+        let mut embed = EmbedBuilder::new().description(description).color(color);
+        embed = match thumbnail {
+            Some(thumbnail) => embed.title(title).thumbnail(thumbnail),
+            None => embed.title(format!("{fallback} {title}")),
+        };
+        // Only available when both users have registered their birthday
+        if let Some((zodiac1, zodiac2)) = zodiacs {
+            let (score, verdict) = zodiac1.compatibility(zodiac2);
+            embed = embed.field(EmbedFieldBuilder::new(
+                "Cung hoàng đạo",
+                format!("{zodiac1} × {zodiac2}: {verdict} ({score}%)"),
+            ));
+        }
+        if let Some((life_path1, life_path2)) = life_paths {
+            let (score, verdict) = Numerology::compatibility(life_path1, life_path2);
+            embed = embed.field(EmbedFieldBuilder::new(
+                "Số chủ đạo",
+                format!("{life_path1} × {life_path2}: {verdict} ({score}%)"),
+            ));
+        }
 
-        vec![EmbedBuilder::new()
-            .title(title)
-            .description(description)
-            .thumbnail(thumbnail)
-            .color(color)
-            .build()]
+        vec![embed.build()]
     }
 }
@@ -0,0 +1,224 @@
+use crate::models::{
+    config::Config,
+    custom_id::CustomId,
+    data_registry::RUNES,
+    seed_generator::{SeedGenerator, TimeHash},
+};
+use anyhow::{Context, Result};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Deserialize;
+use std::{borrow::Cow, ops::Deref, sync::OnceLock};
+use twilight_model::{
+    channel::message::{
+        component::{ActionRow, Button, ButtonStyle},
+        Component, Embed, EmojiReactionType,
+    },
+    id::{marker::UserMarker, Id},
+};
+use twilight_util::builder::embed::{EmbedAuthorBuilder, EmbedBuilder};
+
+type Inner = Box<[Rune]>;
+
+pub struct RuneSet(Inner);
+
+pub struct Rune {
+    name: Box<str>,
+    symbol: Box<str>,
+    meaning: Box<str>,
+    reversed: Option<Box<str>>,
+    full: Box<str>,
+}
+
+#[derive(Deserialize, Clone)]
+struct RuneCow<'a> {
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+    #[serde(borrow)]
+    symbol: Cow<'a, str>,
+    #[serde(borrow)]
+    meaning: Cow<'a, str>,
+    #[serde(borrow)]
+    message: Cow<'a, str>,
+    #[serde(borrow)]
+    warning: Cow<'a, str>,
+    /// Symmetric runes have no merkstave reading
+    #[serde(borrow)]
+    reversed: Option<Cow<'a, str>>,
+}
+
+/// The three Norns weaving past, present and future
+const NORNS: [&str; 3] = ["Urðr · Quá khứ", "Verðandi · Hiện tại", "Skuld · Tương lai"];
+
+static INSTANCE: OnceLock<RuneSet> = OnceLock::new();
+
+impl RuneSet {
+    fn get_instance() -> &'static Self {
+        INSTANCE.get_or_init(|| {
+            Self::load()
+                .unwrap_or_else(|err| panic!("RuneData should be in correct format: {err:#}"))
+        })
+    }
+    /// Load eagerly so a malformed rune set is reported at startup
+    pub fn init() -> Result<()> {
+        if INSTANCE.get().is_none() {
+            let _ = INSTANCE.set(Self::load()?);
+        }
+        Ok(())
+    }
+    fn load() -> Result<Self> {
+        Self::from_json(&RUNES.read()?).context("invalid RuneData")
+    }
+    pub fn from_json(raw: &str) -> Result<Self> {
+        let mut runes = serde_json::from_str::<Vec<RuneCow>>(raw)?
+            .into_iter()
+            .map(
+                |RuneCow {
+                     name,
+                     symbol,
+                     meaning,
+                     message,
+                     warning,
+                     reversed,
+                 }| Rune {
+                    full: format!(
+                        "# [ {symbol} {name} ]\n\
+                    ```md\n\
+                    ## Ý NGHĨA\n{meaning}\n\n\
+                    ## THÔNG ĐIỆP\n{message}\n\n\
+                    ## CẢNH BÁO\n{warning}\n\
+                    ```"
+                    )
+                    .into(),
+                    name: name.into(),
+                    symbol: symbol.into(),
+                    meaning: meaning.into(),
+                    reversed: reversed.map(Into::into),
+                },
+            )
+            .collect::<Vec<_>>();
+        runes.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+
+        Ok(RuneSet(runes.into()))
+    }
+    pub fn draw(
+        config: &Config,
+        content: Option<&str>,
+        author: Id<UserMarker>,
+        amount: Option<usize>,
+        norns: bool,
+    ) -> (Vec<Embed>, Vec<Component>) {
+        let runes = Self::get_instance();
+        let time = match (content, amount) {
+            (None, None) => TimeHash::Day(config.time.utc_offset_minutes),
+            (None, Some(_)) => TimeHash::Second,
+            (Some(_), _) => TimeHash::Minute,
+        };
+        let amount = if norns {
+            NORNS.len()
+        } else {
+            amount.unwrap_or(1)
+        };
+        let state = SeedGenerator::default()
+            .hash_time(time)
+            .hash(author)
+            .hash(content)
+            .hash(amount)
+            .hash(norns)
+            .finish();
+        let mut rng = StdRng::seed_from_u64(state);
+
+        let mut embeds = Vec::with_capacity(amount);
+        let mut components = Vec::with_capacity(amount);
+
+        runes
+            .choose_multiple(&mut rng, amount)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, rune)| {
+                // Merkstave only applies to runes with a reversed reading
+                let reversed = rune.reversed.is_some() && rng.gen_bool(0.5);
+                let position = norns.then(|| NORNS[idx]);
+                Self::short(config, rune, reversed, position)
+            })
+            .for_each(|(embed, component)| {
+                embeds.push(embed);
+                components.push(component);
+            });
+        let components = vec![Component::ActionRow(ActionRow { components })];
+
+        (embeds, components)
+    }
+    fn short(
+        config: &Config,
+        Rune {
+            name,
+            symbol,
+            meaning,
+            reversed: reversed_meaning,
+            ..
+        }: &Rune,
+        reversed: bool,
+        position: Option<&str>,
+    ) -> (Embed, Component) {
+        let custom_id = CustomId::ButtonRuneInfo(Cow::Borrowed(name), reversed).to_string();
+        let (title, description) = match reversed_meaning {
+            Some(reversed_meaning) if reversed => {
+                (format!("{symbol} {name} (ngược)"), reversed_meaning)
+            }
+            _ => (format!("{symbol} {name}"), meaning),
+        };
+
+        let mut embed = EmbedBuilder::new()
+            .title(&title)
+            .description(description.as_ref())
+            .color(config.colors.primary);
+        if let Some(position) = position {
+            embed = embed.author(EmbedAuthorBuilder::new(position));
+        }
+        let component = Component::Button(Button {
+            custom_id: Some(custom_id),
+            disabled: false,
+            emoji: Some(EmojiReactionType::Unicode {
+                name: "📜".to_owned(),
+            }),
+            label: Some(title),
+            style: ButtonStyle::Secondary,
+            url: None,
+            sku_id: None,
+        });
+        (embed.build(), component)
+    }
+    fn long(
+        Rune {
+            full,
+            reversed: reversed_meaning,
+            ..
+        }: &Rune,
+        reversed: bool,
+    ) -> Cow<'_, str> {
+        match reversed_meaning {
+            // Append the merkstave reading inside the same code block
+            Some(reversed_meaning) if reversed => Cow::Owned(format!(
+                "{}\n\n## NGƯỢC (MERKSTAVE)\n{reversed_meaning}\n```",
+                full.trim_end_matches("```").trim_end()
+            )),
+            _ => Cow::Borrowed(full),
+        }
+    }
+    pub fn long_by_name(name: &str, reversed: bool) -> Option<Cow<'static, str>> {
+        let runes = Self::get_instance();
+        let pos = runes
+            .binary_search_by(|rune| rune.name.as_ref().cmp(name))
+            .ok()?;
+        Some(Self::long(&runes.0[pos], reversed))
+    }
+}
+
+impl Deref for RuneSet {
+    type Target = Inner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
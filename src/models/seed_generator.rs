@@ -8,7 +8,8 @@ pub enum TimeHash {
     Minute,
     // Hour,
     Second,
-    Day,
+    /// Rolls over at midnight of the given UTC offset, in minutes
+    Day(i32),
 }
 
 impl SeedGenerator {
@@ -25,9 +26,8 @@ impl SeedGenerator {
         let mut mod_unix_secs = unix_secs;
         let sub = match kind {
             TimeHash::Minute => MINUTE,
-            TimeHash::Day => {
-                // Make GMT+7 the root
-                mod_unix_secs += 7 * HOUR;
+            TimeHash::Day(utc_offset) => {
+                mod_unix_secs = unix_secs.saturating_add_signed(i64::from(utc_offset) * 60);
                 DAY
             }
             TimeHash::Second => SECOND,
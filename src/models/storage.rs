@@ -0,0 +1,123 @@
+use crate::models::{
+    blocklist::Blocklist,
+    book_of_answers::{BookOfAnswers, ToneDistribution},
+    clow_cards::ClowCardDeck,
+    command_toggles::CommandToggles,
+    zodiac::BirthDate,
+};
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{RwLock, RwLockReadGuard},
+};
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
+
+/// Small JSON-backed store for data that has to survive restarts.
+///
+/// Every mutation is written through to disk immediately.
+#[derive(Debug)]
+pub struct Storage {
+    path: PathBuf,
+    inner: RwLock<StorageData>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageData {
+    pub birthdays: FxHashMap<Id<UserMarker>, BirthDate>,
+    pub oracle_tones: FxHashMap<Id<GuildMarker>, ToneDistribution>,
+    /// Custom answer books uploaded by each guild, keyed by book name
+    pub answer_books: FxHashMap<Id<GuildMarker>, BTreeMap<String, BookOfAnswers>>,
+    /// Card decks uploaded by each guild, keyed by deck id
+    pub card_decks: FxHashMap<Id<GuildMarker>, BTreeMap<String, ClowCardDeck>>,
+    /// Commands turned off by each guild, in the whole guild or per channel
+    pub command_toggles: FxHashMap<Id<GuildMarker>, CommandToggles>,
+    pub blocklist: Blocklist,
+}
+
+impl Storage {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let data = match std::fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => StorageData::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            path,
+            inner: RwLock::new(data),
+        })
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, StorageData> {
+        self.inner
+            .read()
+            .expect("Storage lock should not be poisoned")
+    }
+
+    /// Apply `f` to a copy of the stored data, persist it, then make it current.
+    /// The stored data is left untouched when saving fails.
+    pub fn update<T>(&self, f: impl FnOnce(&mut StorageData) -> T) -> Result<T> {
+        let mut data = self
+            .inner
+            .write()
+            .expect("Storage lock should not be poisoned");
+        let mut next = data.clone();
+        let output = f(&mut next);
+        Self::save(&self.path, &next)?;
+        *data = next;
+        Ok(output)
+    }
+
+    /// Persist the current data, every update is already saved so this only matters on shutdown
+    pub fn flush(&self) -> Result<()> {
+        Self::save(&self.path, &self.read())
+    }
+
+    fn save(path: &Path, data: &StorageData) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write to a sibling file first so a crash never leaves a half-written store
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(data)?)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_save_keeps_old_data() {
+        let dir = std::env::temp_dir().join(format!("boothebot-storage-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("storage.json");
+        let storage = Storage::open(&path).unwrap();
+        let user = Id::new(1);
+        let date = BirthDate::new(1, 1).unwrap();
+        storage
+            .update(|data| data.birthdays.insert(user, date))
+            .unwrap();
+
+        // A directory in place of the temporary file makes the write fail
+        std::fs::create_dir_all(path.with_extension("tmp")).unwrap();
+        let other = BirthDate::new(2, 2).unwrap();
+        assert!(storage
+            .update(|data| data.birthdays.insert(user, other))
+            .is_err());
+        assert_eq!(storage.read().birthdays.get(&user), Some(&date));
+
+        let reopened = Storage::open(&path).unwrap();
+        assert_eq!(reopened.read().birthdays.get(&user), Some(&date));
+    }
+}
@@ -0,0 +1,272 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BirthDate {
+    day: u8,
+    month: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    year: Option<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Zodiac {
+    Aries,
+    Taurus,
+    Gemini,
+    Cancer,
+    Leo,
+    Virgo,
+    Libra,
+    Scorpio,
+    Sagittarius,
+    Capricorn,
+    Aquarius,
+    Pisces,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Element {
+    Fire,
+    Earth,
+    Air,
+    Water,
+}
+
+pub struct ZodiacInfo {
+    pub name: &'static str,
+    pub symbol: &'static str,
+    pub dates: &'static str,
+    pub element: Element,
+}
+
+const ZODIAC_INFOS: &[ZodiacInfo] = &[
+    ZodiacInfo {
+        name: "Bạch Dương",
+        symbol: "♈",
+        dates: "21/03 - 19/04",
+        element: Element::Fire,
+    },
+    ZodiacInfo {
+        name: "Kim Ngưu",
+        symbol: "♉",
+        dates: "20/04 - 20/05",
+        element: Element::Earth,
+    },
+    ZodiacInfo {
+        name: "Song Tử",
+        symbol: "♊",
+        dates: "21/05 - 20/06",
+        element: Element::Air,
+    },
+    ZodiacInfo {
+        name: "Cự Giải",
+        symbol: "♋",
+        dates: "21/06 - 22/07",
+        element: Element::Water,
+    },
+    ZodiacInfo {
+        name: "Sư Tử",
+        symbol: "♌",
+        dates: "23/07 - 22/08",
+        element: Element::Fire,
+    },
+    ZodiacInfo {
+        name: "Xử Nữ",
+        symbol: "♍",
+        dates: "23/08 - 22/09",
+        element: Element::Earth,
+    },
+    ZodiacInfo {
+        name: "Thiên Bình",
+        symbol: "♎",
+        dates: "23/09 - 22/10",
+        element: Element::Air,
+    },
+    ZodiacInfo {
+        name: "Bọ Cạp",
+        symbol: "♏",
+        dates: "23/10 - 21/11",
+        element: Element::Water,
+    },
+    ZodiacInfo {
+        name: "Nhân Mã",
+        symbol: "♐",
+        dates: "22/11 - 21/12",
+        element: Element::Fire,
+    },
+    ZodiacInfo {
+        name: "Ma Kết",
+        symbol: "♑",
+        dates: "22/12 - 19/01",
+        element: Element::Earth,
+    },
+    ZodiacInfo {
+        name: "Bảo Bình",
+        symbol: "♒",
+        dates: "20/01 - 18/02",
+        element: Element::Air,
+    },
+    ZodiacInfo {
+        name: "Song Ngư",
+        symbol: "♓",
+        dates: "19/02 - 20/03",
+        element: Element::Water,
+    },
+];
+
+const ZODIACS: [Zodiac; 12] = [
+    Zodiac::Aries,
+    Zodiac::Taurus,
+    Zodiac::Gemini,
+    Zodiac::Cancer,
+    Zodiac::Leo,
+    Zodiac::Virgo,
+    Zodiac::Libra,
+    Zodiac::Scorpio,
+    Zodiac::Sagittarius,
+    Zodiac::Capricorn,
+    Zodiac::Aquarius,
+    Zodiac::Pisces,
+];
+
+impl BirthDate {
+    pub fn new(day: u8, month: u8) -> Option<Self> {
+        // Leap day is allowed since the year is unknown
+        const DAYS_IN_MONTH: [u8; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+        let max_day = *DAYS_IN_MONTH.get(usize::from(month).checked_sub(1)?)?;
+        (1..=max_day).contains(&day).then_some(Self {
+            day,
+            month,
+            year: None,
+        })
+    }
+    pub fn with_year(self, year: u16) -> Option<Self> {
+        let leap =
+            (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+        let valid = (1..=9999).contains(&year) && (leap || (self.day, self.month) != (29, 2));
+        valid.then_some(Self {
+            year: Some(year),
+            ..self
+        })
+    }
+    pub fn day(self) -> u8 {
+        self.day
+    }
+    pub fn month(self) -> u8 {
+        self.month
+    }
+    pub fn year(self) -> Option<u16> {
+        self.year
+    }
+    pub fn zodiac(self) -> Zodiac {
+        // The day each sign begins, indexed by month
+        const CUTOFFS: [u8; 12] = [20, 19, 21, 20, 21, 21, 23, 23, 23, 23, 22, 22];
+        let month = usize::from(self.month);
+        let idx = if self.day >= CUTOFFS[month - 1] {
+            (month + 9) % 12
+        } else {
+            (month + 8) % 12
+        };
+        ZODIACS[idx]
+    }
+}
+
+impl FromStr for BirthDate {
+    type Err = anyhow::Error;
+
+    /// Accept `dd/mm`, `dd-mm` and optionally a trailing `/yyyy`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(['/', '-']).map(str::trim);
+        let (Some(day), Some(month)) = (parts.next(), parts.next()) else {
+            anyhow::bail!("birth date should be `dd/mm`: `{s}`");
+        };
+        let date = Self::new(day.parse()?, month.parse()?);
+        let date = match (parts.next(), parts.next()) {
+            (None, _) => date,
+            (Some(year), None) => date.and_then(|date| date.with_year(year.parse().ok()?)),
+            _ => None,
+        };
+        date.ok_or_else(|| anyhow::anyhow!("invalid birth date: `{s}`"))
+    }
+}
+
+impl Display for BirthDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}/{:02}", self.day, self.month)?;
+        if let Some(year) = self.year {
+            write!(f, "/{year:04}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Zodiac {
+    pub fn info(self) -> &'static ZodiacInfo {
+        &ZODIAC_INFOS[self as usize]
+    }
+    /// Element based compatibility, returns a score (0-100) and a short verdict
+    pub fn compatibility(self, other: Self) -> (u8, &'static str) {
+        use Element::*;
+        let (lhs, rhs) = (self.info().element, other.info().element);
+        match (lhs, rhs) {
+            _ if self == other => (90, "Cùng một cung, hiểu nhau như soi gương"),
+            _ if lhs == rhs => (85, "Cùng nguyên tố, đồng điệu tự nhiên"),
+            (Fire, Air) | (Air, Fire) => (75, "Lửa gặp gió, càng thổi càng bùng cháy"),
+            (Earth, Water) | (Water, Earth) => (75, "Đất gặp nước, nuôi dưỡng lẫn nhau"),
+            (Fire, Water) | (Water, Fire) => (35, "Nước và lửa, cần nhiều nhẫn nại"),
+            _ => (50, "Khác biệt nhưng có thể học hỏi từ nhau"),
+        }
+    }
+}
+
+impl Display for Zodiac {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ZodiacInfo { name, symbol, .. } = self.info();
+        write!(f, "{symbol} {name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u8, month: u8) -> BirthDate {
+        BirthDate::new(day, month).unwrap()
+    }
+
+    #[test]
+    fn zodiac_matches_listed_dates() {
+        for zodiac in ZODIACS {
+            let (start, end) = zodiac.info().dates.split_once(" - ").unwrap();
+            for bound in [start, end] {
+                let bound = bound.parse::<BirthDate>().unwrap();
+                assert_eq!(bound.zodiac(), zodiac, "{bound}");
+            }
+        }
+    }
+
+    #[test]
+    fn zodiac_cutoffs() {
+        assert_eq!(date(19, 1).zodiac(), Zodiac::Capricorn);
+        assert_eq!(date(20, 1).zodiac(), Zodiac::Aquarius);
+        assert_eq!(date(29, 2).zodiac(), Zodiac::Pisces);
+        assert_eq!(date(20, 3).zodiac(), Zodiac::Pisces);
+        assert_eq!(date(21, 3).zodiac(), Zodiac::Aries);
+        assert_eq!(date(21, 12).zodiac(), Zodiac::Sagittarius);
+        assert_eq!(date(22, 12).zodiac(), Zodiac::Capricorn);
+        assert_eq!(date(31, 12).zodiac(), Zodiac::Capricorn);
+    }
+
+    #[test]
+    fn parse_birth_date() {
+        assert_eq!("05/11".parse::<BirthDate>().unwrap(), date(5, 11));
+        assert_eq!(
+            "29-02-2000".parse::<BirthDate>().unwrap(),
+            date(29, 2).with_year(2000).unwrap()
+        );
+        assert!("29/02/1900".parse::<BirthDate>().is_err());
+        assert!("31/04".parse::<BirthDate>().is_err());
+        assert!("1/1/2000/1".parse::<BirthDate>().is_err());
+    }
+}
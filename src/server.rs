@@ -0,0 +1,58 @@
+use crate::models::app_state::AppState;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use std::{net::SocketAddr, time::Duration};
+use tracing::{info, warn};
+
+/// Serve the monitoring endpoints until the process exits
+pub async fn serve(state: AppState, listen: SocketAddr) {
+    let app = Router::new()
+        .route("/metrics", get(metrics))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(state);
+    let listener = match tokio::net::TcpListener::bind(listen).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            warn!(?err, %listen, "unable to bind the monitoring server");
+            return;
+        }
+    };
+    info!(%listen, "serving metrics and probes");
+    let _ = axum::serve(listener, app)
+        .await
+        .inspect_err(|err| warn!(?err, "monitoring server stopped"));
+}
+
+async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        state.metrics.encode(),
+    )
+}
+
+/// Fails once the gateway has been disconnected longer than `server.unhealthy_after_secs`
+async fn healthz(State(state): State<AppState>) -> impl IntoResponse {
+    let threshold = Duration::from_secs(state.config.server.unhealthy_after_secs);
+    match state.health.disconnected_for() {
+        Some(elapsed) if elapsed > threshold => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("gateway disconnected for {}s", elapsed.as_secs()),
+        ),
+        _ => (StatusCode::OK, "ok".to_owned()),
+    }
+}
+
+/// Ready once connected to the gateway and the commands are registered
+async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+    if state.health.is_ready() {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not ready")
+    }
+}
@@ -0,0 +1,268 @@
+use crate::models::{
+    assets::Assets,
+    book_of_answers::BookOfAnswers,
+    clow_cards::{ClowCardDeck, BUILTIN_DECKS},
+    data_registry::{
+        About, ABOUT, ASSETS, BOOK_OF_ANSWERS, HOROSCOPE, I_CHING, LEGACY_BOOK_OF_ANSWERS,
+        NUMEROLOGY, RUNES,
+    },
+    horoscope::Horoscope,
+    i_ching::IChing,
+    numerology::Numerology,
+    runes::RuneSet,
+};
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+/// Discord limits the datasets end up in
+mod discord {
+    pub const CONTENT: usize = 2000;
+    pub const EMBED_TITLE: usize = 256;
+    pub const EMBED_DESCRIPTION: usize = 4096;
+    pub const EMBED_FIELD: usize = 1024;
+    pub const BUTTON_LABEL: usize = 80;
+}
+
+/// Text fields and the most characters each may have once rendered
+type Limits = &'static [(&'static str, usize)];
+
+const CARD_LIMITS: Limits = &[("meaning", discord::EMBED_DESCRIPTION)];
+const BOOK_LIMITS: Limits = &[("vi", discord::CONTENT), ("en", discord::CONTENT)];
+const HOROSCOPE_LIMITS: Limits = &[
+    ("overview", discord::EMBED_DESCRIPTION),
+    ("love", discord::EMBED_FIELD),
+    ("work", discord::EMBED_FIELD),
+    ("advice", discord::EMBED_FIELD),
+    ("colors", discord::EMBED_FIELD),
+];
+const I_CHING_LIMITS: Limits = &[
+    ("name", discord::EMBED_TITLE),
+    ("judgment", discord::EMBED_FIELD),
+    ("changing", discord::EMBED_FIELD),
+];
+const NUMEROLOGY_LIMITS: Limits = &[
+    ("title", discord::EMBED_TITLE),
+    ("meaning", discord::EMBED_FIELD),
+];
+const RUNE_LIMITS: Limits = &[
+    // Drawn runes are listed as `symbol name` buttons
+    ("name", discord::BUTTON_LABEL - 16),
+    ("symbol", 8),
+    ("meaning", discord::EMBED_DESCRIPTION),
+    ("message", discord::CONTENT),
+    ("warning", discord::CONTENT),
+    ("reversed", discord::CONTENT),
+];
+
+#[derive(Default)]
+struct Report {
+    issues: Vec<String>,
+    checked: usize,
+}
+
+impl Report {
+    fn issue(&mut self, file: &str, message: impl Display) {
+        self.issues.push(format!("{file}: {message}"));
+    }
+    /// Run the loader the bot uses on `file`, returns the raw text and the result when valid
+    fn load<T>(
+        &mut self,
+        dir: &Path,
+        file: &str,
+        loader: impl FnOnce(&str) -> Result<T>,
+    ) -> Option<(String, T)> {
+        self.checked += 1;
+        let raw = std::fs::read_to_string(dir.join(file))
+            .inspect_err(|err| self.issue(file, format_args!("unable to read: {err}")))
+            .ok()?;
+        match loader(&raw) {
+            Ok(data) => Some((raw, data)),
+            Err(err) => {
+                self.issue(file, format_args!("{err:#}"));
+                None
+            }
+        }
+    }
+    /// Check the text fields of every entry against the Discord limits
+    fn limits(&mut self, file: &str, raw: &str, limits: Limits) {
+        let entries = match serde_json::from_str::<Value>(raw) {
+            Ok(Value::Array(entries)) => entries,
+            Ok(corpus) => vec![corpus],
+            Err(err) => return self.issue(file, format_args!("malformed JSON: {err}")),
+        };
+        for (idx, entry) in entries.iter().enumerate() {
+            let name = match entry.get("name").and_then(Value::as_str) {
+                Some(name) => format!("`{name}` "),
+                None if entries.len() > 1 => format!("entry #{idx} "),
+                None => String::new(),
+            };
+            for &(field, max) in limits {
+                match entry.get(field) {
+                    Some(Value::String(text)) => {
+                        self.check_text(file, &format!("{name}`{field}`"), text, max)
+                    }
+                    Some(Value::Array(texts)) => {
+                        for (pos, text) in texts.iter().enumerate() {
+                            if let Some(text) = text.as_str() {
+                                self.check_text(file, &format!("{name}`{field}` #{pos}"), text, max)
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    fn check_text(&mut self, file: &str, what: &str, text: &str, max: usize) {
+        if text.trim().is_empty() {
+            self.issue(file, format_args!("{what} is empty"));
+        }
+        let len = text.chars().count();
+        if len > max {
+            self.issue(
+                file,
+                format_args!("{what} has {len} characters, the limit is {max}"),
+            );
+        }
+        if let Some(problem) = markdown_problem(text) {
+            self.issue(file, format_args!("{what} {problem}"));
+        }
+    }
+}
+
+/// Texts are rendered inside bold markers or code blocks, so these would break the layout
+fn markdown_problem(text: &str) -> Option<String> {
+    if text.contains("```") {
+        return Some("contains a code fence".to_owned());
+    }
+    ["**", "__", "~~", "||"]
+        .into_iter()
+        .find(|marker| text.matches(marker).count() % 2 == 1)
+        .map(|marker| format!("has an unbalanced `{marker}`"))
+}
+
+fn check_book(report: &mut Report, dir: &Path) {
+    // Same precedence as the bot, the legacy format is only used without a JSON book
+    if !dir.join(BOOK_OF_ANSWERS.name).exists() && dir.join(LEGACY_BOOK_OF_ANSWERS).exists() {
+        let file = LEGACY_BOOK_OF_ANSWERS;
+        let converted = report
+            .load(dir, file, BookOfAnswers::from_legacy)
+            .map(|(_, book)| book.to_json());
+        match converted {
+            Some(Ok(raw)) => report.limits(file, &raw, BOOK_LIMITS),
+            Some(Err(err)) => report.issue(file, format_args!("{err:#}")),
+            None => {}
+        }
+        return;
+    }
+    if let Some((raw, _)) = report.load(dir, BOOK_OF_ANSWERS.name, BookOfAnswers::from_json) {
+        report.limits(BOOK_OF_ANSWERS.name, &raw, BOOK_LIMITS);
+    }
+}
+
+/// Check every dataset found in `dir`
+fn check(dir: &Path) -> Report {
+    let mut report = Report::default();
+
+    for deck in BUILTIN_DECKS {
+        let file = deck.file.name;
+        let loader = |raw: &str| ClowCardDeck::from_json(raw, deck.title_prefix);
+        if let Some((raw, _)) = report.load(dir, file, loader) {
+            report.limits(file, &raw, CARD_LIMITS);
+        }
+    }
+    check_book(&mut report, dir);
+    for (file, loader, limits) in [
+        (
+            HOROSCOPE.name,
+            (|raw| Horoscope::from_json(raw).map(drop)) as fn(&str) -> Result<()>,
+            HOROSCOPE_LIMITS,
+        ),
+        (
+            I_CHING.name,
+            |raw| IChing::from_json(raw).map(drop),
+            I_CHING_LIMITS,
+        ),
+        (
+            NUMEROLOGY.name,
+            |raw| Numerology::from_json(raw).map(drop),
+            NUMEROLOGY_LIMITS,
+        ),
+        (
+            RUNES.name,
+            |raw| RuneSet::from_json(raw).map(drop),
+            RUNE_LIMITS,
+        ),
+    ] {
+        if let Some((raw, _)) = report.load(dir, file, loader) {
+            report.limits(file, &raw, limits);
+        }
+    }
+    report.load(dir, ABOUT.name, About::from_text);
+    // Optional, missing assets fall back to Unicode
+    if dir.join(ASSETS.name).exists() {
+        report.load(dir, ASSETS.name, Assets::from_json);
+    }
+    report
+}
+
+/// Check every dataset in `dir`, printing each issue found
+pub fn run(dir: Option<PathBuf>) -> ExitCode {
+    let dir = dir.unwrap_or_else(|| PathBuf::from("static"));
+    let report = check(&dir);
+
+    report.issues.iter().for_each(|issue| eprintln!("{issue}"));
+    println!(
+        "checked {} files in `{}`, found {} issues",
+        report.checked,
+        dir.display(),
+        report.issues.len()
+    );
+    if report.issues.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_data_is_valid() {
+        let report = check(Path::new("static"));
+        assert_eq!(report.issues, Vec::<String>::new());
+    }
+
+    #[test]
+    fn duplicated_card_is_reported() {
+        let dir = std::env::temp_dir().join(format!("boothebot-validate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let card = r#"{"name": "Windy", "meaning": "a", "message": "b", "warning": "c"}"#;
+        std::fs::write(dir.join("ClowCardData.json"), format!("[{card}, {card}]")).unwrap();
+
+        let report = check(&dir);
+        assert!(report
+            .issues
+            .contains(&"ClowCardData.json: card `Windy` is duplicated".to_owned()));
+    }
+
+    #[test]
+    fn long_text_is_reported() {
+        let mut report = Report::default();
+        let meaning = "a".repeat(discord::EMBED_FIELD + 1);
+        let raw = format!(r#"[{{"number": 1, "title": "Một", "meaning": "{meaning}"}}]"#);
+        report.limits("Numerology.json", &raw, NUMEROLOGY_LIMITS);
+        assert_eq!(
+            report.issues,
+            ["Numerology.json: `meaning` has 1025 characters, the limit is 1024"]
+        );
+    }
+}